@@ -0,0 +1,141 @@
+// Compares each SIMD kernel, at every level this cpu supports, with the
+// function the crate used for that operation before it had them:
+//
+//     cargo bench --bench simd
+//
+// `vec_bin_op` and `vec_bin_op_mut` for the elementwise f64 and f32 ops,
+// `vec_fn_op_mut` for scale and exp (what `Matrix2d::scale` and `apply_fn`
+// ran), the scalar `unrolled_sum` for sum and the fold `par_max_axis` runs
+// for max. `unrolled_sum` itself now runs on the kernels, so its baseline
+// is the scalar level, which is the loop it used to be.
+extern crate criterion;
+extern crate num_rust;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use num_rust::simd::{self, Level};
+use num_rust::utils::{unrolled_sum, vec_bin_op, vec_bin_op_mut, vec_fn_op_mut};
+
+const LEN: usize = 1 << 16;
+
+fn levels() -> Vec<Level> {
+    vec![Level::Scalar, Level::Sse2, Level::Avx2, Level::Avx512].into_iter()
+        .filter(|&l| l <= simd::detected_level())
+        .collect()
+}
+
+fn inputs() -> (Vec<f64>, Vec<f64>) {
+    let u = (0..LEN).map(|i| (i as f64 * 0.37).sin() * 10.).collect();
+    let v = (0..LEN).map(|i| 1. + (i % 13) as f64).collect();
+    (u, v)
+}
+
+// `baseline` under its own name, then `kernel` at each level
+fn compare<B, K, R>(c: &mut Criterion, group: &str, name: &str, mut baseline: B, mut kernel: K)
+    where B: FnMut() -> R, K: FnMut() -> R
+{
+    let mut group = c.benchmark_group(group);
+    group.throughput(Throughput::Elements(LEN as u64));
+    group.bench_function(name, |b| b.iter(&mut baseline));
+    for level in levels() {
+        simd::set_level(Some(level));
+        group.bench_function(BenchmarkId::new("simd", format!("{:?}", level)), |b| b.iter(&mut kernel));
+    }
+    simd::set_level(None);
+    group.finish();
+}
+
+fn elementwise_f64(c: &mut Criterion) {
+    let (u, v) = inputs();
+    let (u, v) = (&u, &v);
+
+    // `vec_bin_op` allocates its output without initializing it, so the
+    // kernels do as well, as `Matrix2d::addition` and the like do
+    macro_rules! bin_op {
+        ($group:expr, $kernel:path, $op:expr) => {
+            compare(c, $group, "vec_bin_op",
+                    || vec_bin_op(black_box(u), black_box(v), $op),
+                    || {
+                        let mut dst = Vec::with_capacity(LEN);
+                        unsafe {
+                            dst.set_len(LEN);
+                        }
+                        $kernel(black_box(u), black_box(v), &mut dst);
+                        dst
+                    })
+        }
+    }
+    bin_op!("add_f64", simd::add, |x, y| x + y);
+    bin_op!("sub_f64", simd::sub, |x, y| x - y);
+    bin_op!("mul_f64", simd::mul, |x, y| x * y);
+    bin_op!("div_f64", simd::div, |x, y| x / y);
+
+    // these write into one buffer, which both sides borrow mutably
+    let dst = &mut vec![0.; LEN];
+    macro_rules! un_op {
+        ($group:expr, $op:expr, $kernel:expr) => {{
+            let mut group = c.benchmark_group($group);
+            group.throughput(Throughput::Elements(LEN as u64));
+            group.bench_function("vec_fn_op_mut", |b| b.iter(|| vec_fn_op_mut(black_box(u), dst, &$op)));
+            for level in levels() {
+                simd::set_level(Some(level));
+                group.bench_function(BenchmarkId::new("simd", format!("{:?}", level)),
+                                     |b| b.iter(|| $kernel(black_box(u), dst)));
+            }
+            simd::set_level(None);
+            group.finish();
+        }}
+    }
+    un_op!("scale_f64", |x| x * 2.5, |u, dst| simd::scale(u, 2.5, dst));
+    un_op!("exp_f64", f64::exp, simd::exp);
+}
+
+fn elementwise_f32(c: &mut Criterion) {
+    let (u, v) = inputs();
+    let u = &u.iter().map(|&x| x as f32).collect::<Vec<f32>>();
+    let v = &v.iter().map(|&x| x as f32).collect::<Vec<f32>>();
+    let dst = &mut vec![0f32; LEN];
+
+    macro_rules! bin_op {
+        ($group:expr, $kernel:path, $op:expr) => {{
+            let mut group = c.benchmark_group($group);
+            group.throughput(Throughput::Elements(LEN as u64));
+            group.bench_function("vec_bin_op_mut",
+                                 |b| b.iter(|| vec_bin_op_mut(black_box(u), black_box(v), LEN, dst, &$op)));
+            for level in levels() {
+                simd::set_level(Some(level));
+                group.bench_function(BenchmarkId::new("simd", format!("{:?}", level)),
+                                     |b| b.iter(|| $kernel(black_box(u), black_box(v), dst)));
+            }
+            simd::set_level(None);
+            group.finish();
+        }}
+    }
+    bin_op!("add_f32", simd::add, |x: f32, y: f32| x + y);
+    bin_op!("sub_f32", simd::sub, |x: f32, y: f32| x - y);
+    bin_op!("mul_f32", simd::mul, |x: f32, y: f32| x * y);
+    bin_op!("div_f32", simd::div, |x: f32, y: f32| x / y);
+}
+
+fn reductions(c: &mut Criterion) {
+    let (u, _) = inputs();
+    let u = &u;
+
+    let mut group = c.benchmark_group("sum_f64");
+    group.throughput(Throughput::Elements(LEN as u64));
+    simd::set_level(Some(Level::Scalar));
+    group.bench_function("unrolled_sum", |b| b.iter(|| unrolled_sum(black_box(u))));
+    for level in levels() {
+        simd::set_level(Some(level));
+        group.bench_function(BenchmarkId::new("simd", format!("{:?}", level)),
+                             |b| b.iter(|| simd::sum(black_box(u))));
+    }
+    simd::set_level(None);
+    group.finish();
+
+    compare(c, "max_f64", "fold",
+            || black_box(u).iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            || simd::max(black_box(u)));
+}
+
+criterion_group!(benches, elementwise_f64, elementwise_f32, reductions);
+criterion_main!(benches);
@@ -0,0 +1,271 @@
+use Matrix2d;
+use utils::{sum_rows, sigmoid};
+
+use std::cell::RefCell;
+
+// Reverse-mode autodiff: every operation on a `Var` appends a node to its
+// `Tape`, `backward` then walks the tape from the end to the leaves.
+
+enum Op {
+    Leaf,
+    Dot(usize, usize),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mult(usize, usize),
+    AddRow(usize, usize),
+    Scale(usize, f64),
+    Transpose(usize),
+    Sum(usize),
+    Mean(usize),
+    SumRows(usize),
+    Sigmoid(usize),
+    Tanh(usize),
+    Relu(usize),
+    Exp(usize),
+    Ln(usize),
+}
+
+struct Node {
+    op: Op,
+    value: Matrix2d,
+}
+
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Var<'t> {
+    tape: &'t Tape,
+    index: usize,
+}
+
+pub struct Gradients {
+    grads: Vec<Option<Matrix2d>>,
+}
+
+fn filled(n_rows: usize, n_cols: usize, x: f64) -> Matrix2d {
+    Matrix2d::reshape_from_vec(&vec![x; n_rows * n_cols], n_rows, n_cols).unwrap()
+}
+
+fn broadcast_rows(row: &Matrix2d, n_rows: usize) -> Matrix2d {
+    let vec = (0..n_rows)
+        .flat_map(|_| row.get_row(0).unwrap())
+        .collect::<Vec<f64>>();
+    Matrix2d::reshape_from_vec(&vec, n_rows, row.get_cols()).unwrap()
+}
+
+fn accumulate(grads: &mut [Option<Matrix2d>], index: usize, g: Matrix2d) {
+    let sum = match grads[index].take() {
+        Some(acc) => acc.addition(&g).unwrap(),
+        None => g,
+    };
+    grads[index] = Some(sum);
+}
+
+impl Tape {
+    pub fn new() -> Tape {
+        Tape { nodes: RefCell::new(Vec::new()) }
+    }
+
+    // values on the tape are kept row-major so the elementwise ops line up
+    pub fn var(&self, value: &Matrix2d) -> Var<'_> {
+        self.push(Op::Leaf, value.as_standard_layout())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&self, op: Op, value: Matrix2d) -> Var<'_> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { op, value });
+        Var { tape: self, index: nodes.len() - 1 }
+    }
+}
+
+impl Default for Tape {
+    fn default() -> Tape {
+        Tape::new()
+    }
+}
+
+impl<'t> Var<'t> {
+    pub fn value(&self) -> Matrix2d {
+        self.tape.nodes.borrow()[self.index].value.clone()
+    }
+
+    fn unary<F>(&self, op: Op, f: F) -> Var<'t>
+        where F: Fn(&Matrix2d) -> Matrix2d
+    {
+        let value = f(&self.tape.nodes.borrow()[self.index].value);
+        self.tape.push(op, value)
+    }
+
+    fn binary<F>(&self, other: &Var<'t>, op: Op, f: F) -> Option<Var<'t>>
+        where F: Fn(&Matrix2d, &Matrix2d) -> Option<Matrix2d>
+    {
+        let value = {
+            let nodes = self.tape.nodes.borrow();
+            f(&nodes[self.index].value, &nodes[other.index].value)
+        };
+        value.map(|v| self.tape.push(op, v))
+    }
+
+    pub fn dot(&self, other: &Var<'t>) -> Option<Var<'t>> {
+        self.binary(other, Op::Dot(self.index, other.index), |a, b| a.dot(b))
+    }
+
+    pub fn addition(&self, other: &Var<'t>) -> Option<Var<'t>> {
+        self.binary(other, Op::Add(self.index, other.index), |a, b| a.addition(b))
+    }
+
+    pub fn subtract(&self, other: &Var<'t>) -> Option<Var<'t>> {
+        self.binary(other, Op::Sub(self.index, other.index), |a, b| a.subtract(b))
+    }
+
+    pub fn mult(&self, other: &Var<'t>) -> Option<Var<'t>> {
+        self.binary(other, Op::Mult(self.index, other.index), |a, b| a.mult(b))
+    }
+
+    // adds a 1 x n_cols row (e.g. a bias) to every row
+    pub fn add_row(&self, row: &Var<'t>) -> Option<Var<'t>> {
+        self.binary(row, Op::AddRow(self.index, row.index), |a, r| {
+            if r.get_rows() != 1 {
+                return None;
+            }
+            a.addition(&broadcast_rows(r, a.get_rows()))
+        })
+    }
+
+    pub fn scale(&self, scalar: f64) -> Var<'t> {
+        self.unary(Op::Scale(self.index, scalar), |a| a.scale(scalar))
+    }
+
+    pub fn transpose(&self) -> Var<'t> {
+        self.unary(Op::Transpose(self.index), |a| a.transpose().as_standard_layout())
+    }
+
+    pub fn sum(&self) -> Var<'t> {
+        self.unary(Op::Sum(self.index), |a| filled(1, 1, a.get_matrix().iter().sum()))
+    }
+
+    pub fn mean(&self) -> Var<'t> {
+        self.unary(Op::Mean(self.index), |a| {
+            filled(1, 1, a.get_matrix().iter().sum::<f64>() / a.get_matrix().len() as f64)
+        })
+    }
+
+    pub fn sum_rows(&self) -> Var<'t> {
+        self.unary(Op::SumRows(self.index), sum_rows)
+    }
+
+    pub fn sigmoid(&self) -> Var<'t> {
+        self.unary(Op::Sigmoid(self.index), |a| a.apply_fn(sigmoid))
+    }
+
+    pub fn tanh(&self) -> Var<'t> {
+        self.unary(Op::Tanh(self.index), |a| a.apply_fn(f64::tanh))
+    }
+
+    pub fn relu(&self) -> Var<'t> {
+        self.unary(Op::Relu(self.index), |a| a.apply_fn(|x| x.max(0.)))
+    }
+
+    pub fn exp(&self) -> Var<'t> {
+        self.unary(Op::Exp(self.index), |a| a.apply_fn(f64::exp))
+    }
+
+    pub fn ln(&self) -> Var<'t> {
+        self.unary(Op::Ln(self.index), |a| a.apply_fn(f64::ln))
+    }
+
+    // seeds with ones, i.e. differentiates the sum of this var's elements
+    pub fn backward(&self) -> Gradients {
+        let nodes = self.tape.nodes.borrow();
+        let mut grads: Vec<Option<Matrix2d>> = (0..nodes.len()).map(|_| None).collect();
+
+        let value = &nodes[self.index].value;
+        grads[self.index] = Some(filled(value.get_rows(), value.get_cols(), 1.));
+
+        for idx in (0..self.index + 1).rev() {
+            let g = match grads[idx].take() {
+                Some(g) => g,
+                None => continue,
+            };
+            let y = &nodes[idx].value;
+
+            match nodes[idx].op {
+                Op::Leaf => {}
+                Op::Dot(a, b) => {
+                    let ga = g.dot(&nodes[b].value.transpose()).unwrap();
+                    let gb = nodes[a].value.transpose().dot(&g).unwrap();
+                    accumulate(&mut grads, a, ga);
+                    accumulate(&mut grads, b, gb);
+                }
+                Op::Add(a, b) => {
+                    accumulate(&mut grads, a, g.clone());
+                    accumulate(&mut grads, b, g.clone());
+                }
+                Op::Sub(a, b) => {
+                    accumulate(&mut grads, a, g.clone());
+                    accumulate(&mut grads, b, g.scale(-1.));
+                }
+                Op::Mult(a, b) => {
+                    accumulate(&mut grads, a, g.mult(&nodes[b].value).unwrap());
+                    accumulate(&mut grads, b, g.mult(&nodes[a].value).unwrap());
+                }
+                Op::AddRow(a, r) => {
+                    accumulate(&mut grads, a, g.clone());
+                    accumulate(&mut grads, r, sum_rows(&g));
+                }
+                Op::Scale(a, s) => accumulate(&mut grads, a, g.scale(s)),
+                Op::Transpose(a) => accumulate(&mut grads, a, g.transpose().as_standard_layout()),
+                Op::Sum(a) => {
+                    let x = &nodes[a].value;
+                    let gs = g.get_matrix()[0];
+                    accumulate(&mut grads, a, filled(x.get_rows(), x.get_cols(), gs));
+                }
+                Op::Mean(a) => {
+                    let x = &nodes[a].value;
+                    let gs = g.get_matrix()[0] / x.get_matrix().len() as f64;
+                    accumulate(&mut grads, a, filled(x.get_rows(), x.get_cols(), gs));
+                }
+                Op::SumRows(a) => {
+                    let n_rows = nodes[a].value.get_rows();
+                    accumulate(&mut grads, a, broadcast_rows(&g, n_rows));
+                }
+                Op::Sigmoid(a) => {
+                    accumulate(&mut grads, a, g.mult(&y.apply_fn(|s| s * (1. - s))).unwrap());
+                }
+                Op::Tanh(a) => {
+                    accumulate(&mut grads, a, g.mult(&y.apply_fn(|t| 1. - t * t)).unwrap());
+                }
+                Op::Relu(a) => {
+                    let mask = nodes[a].value.apply_fn(|x| if x > 0. { 1. } else { 0. });
+                    accumulate(&mut grads, a, g.mult(&mask).unwrap());
+                }
+                Op::Exp(a) => accumulate(&mut grads, a, g.mult(y).unwrap()),
+                Op::Ln(a) => {
+                    accumulate(&mut grads, a, g.mult(&nodes[a].value.apply_fn(|x| 1. / x)).unwrap());
+                }
+            }
+
+            if let Op::Leaf = nodes[idx].op {
+                grads[idx] = Some(g);
+            }
+        }
+
+        Gradients { grads }
+    }
+}
+
+impl Gradients {
+    pub fn wrt(&self, var: &Var) -> Option<&Matrix2d> {
+        self.grads.get(var.index).and_then(|g| g.as_ref())
+    }
+}
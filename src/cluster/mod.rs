@@ -0,0 +1,180 @@
+use Matrix2d;
+use pairwise::{pairwise_distances, Metric};
+
+use rand::distributions::{IndependentSample, Range};
+use rand::{SeedableRng, StdRng};
+
+fn squared_distances(x: &Matrix2d, centroids: &Matrix2d) -> Matrix2d {
+    pairwise_distances(x, centroids, Metric::SquaredEuclidean).unwrap()
+}
+
+fn assign(distances: &Matrix2d) -> (Vec<usize>, Vec<f64>) {
+    (0..distances.get_rows())
+        .map(|r| {
+            let row = distances.get_row(r).unwrap();
+            let best = (0..row.len()).fold(0, |b, i| if row[i] < row[b] { i } else { b });
+            (best, row[best])
+        })
+        .unzip()
+}
+
+fn sample_weighted(weights: &[f64], rng: &mut StdRng) -> usize {
+    let total = weights.iter().sum::<f64>();
+    if total <= 0. {
+        return Range::new(0, weights.len()).ind_sample(rng);
+    }
+    let mut target = Range::new(0., total).ind_sample(rng);
+    for (i, w) in weights.iter().enumerate() {
+        if target < *w {
+            return i;
+        }
+        target -= *w;
+    }
+    weights.len() - 1
+}
+
+// k-means++: each new centroid is drawn with probability proportional to its
+// squared distance from the closest centroid picked so far
+fn init_plus_plus(x: &Matrix2d, k: usize, rng: &mut StdRng) -> Matrix2d {
+    let first = Range::new(0, x.get_rows()).ind_sample(rng);
+    let mut chosen = vec![x.get_row(first).unwrap()];
+
+    while chosen.len() < k {
+        let centroids = Matrix2d::from_vec_owned(chosen.concat(), chosen.len(), x.get_cols());
+        let (_, closest) = assign(&squared_distances(x, &centroids));
+        chosen.push(x.get_row(sample_weighted(&closest, rng)).unwrap());
+    }
+    Matrix2d::from_vec_owned(chosen.concat(), k, x.get_cols())
+}
+
+pub struct KMeans {
+    n_clusters: usize,
+    max_iter: usize,
+    tolerance: f64,
+    n_init: usize,
+    seed: Vec<usize>,
+    centroids: Option<Matrix2d>,
+    labels: Vec<usize>,
+    inertia: f64,
+    n_iter: usize,
+}
+
+impl KMeans {
+    pub fn new(n_clusters: usize, seed: &[usize]) -> KMeans {
+        KMeans::with_params(n_clusters, 300, 1e-4, 10, seed)
+    }
+
+    // `tolerance` bounds the total squared centroid movement between iterations
+    pub fn with_params(n_clusters: usize, max_iter: usize, tolerance: f64, n_init: usize, seed: &[usize]) -> KMeans {
+        KMeans {
+            n_clusters,
+            max_iter,
+            tolerance,
+            n_init: n_init.max(1),
+            seed: seed.to_vec(),
+            centroids: None,
+            labels: Vec::new(),
+            inertia: 0.,
+            n_iter: 0,
+        }
+    }
+
+    // runs `n_init` seeded restarts and keeps the one with the lowest inertia
+    pub fn fit(&mut self, x: &Matrix2d) -> Option<()> {
+        let (n, d, k) = (x.get_rows(), x.get_cols(), self.n_clusters);
+        if k == 0 || n < k {
+            return None;
+        }
+
+        let mut rng: StdRng = StdRng::from_seed(&self.seed[..]);
+        let mut best: Option<(Matrix2d, Vec<usize>, f64, usize)> = None;
+
+        for _ in 0..self.n_init {
+            let mut centroids = init_plus_plus(x, k, &mut rng);
+            let mut n_iter = 0;
+
+            for iter in 0..self.max_iter {
+                n_iter = iter + 1;
+                let (labels, closest) = assign(&squared_distances(x, &centroids));
+
+                let mut sums = vec![0.; k * d];
+                let mut counts = vec![0usize; k];
+                for r in 0..n {
+                    counts[labels[r]] += 1;
+                    for (c, v) in x.get_row(r).unwrap().iter().enumerate() {
+                        sums[labels[r] * d + c] += *v;
+                    }
+                }
+
+                // an empty cluster takes over the point furthest from its centroid
+                let mut far = (0..n).collect::<Vec<usize>>();
+                far.sort_by(|&a, &b| closest[b].total_cmp(&closest[a]));
+                let mut far = far.into_iter();
+                for c in 0..k {
+                    if counts[c] == 0 {
+                        let row = x.get_row(far.next().unwrap()).unwrap();
+                        sums[c * d..(c + 1) * d].copy_from_slice(&row);
+                        counts[c] = 1;
+                    }
+                }
+                for c in 0..k {
+                    for v in sums[c * d..(c + 1) * d].iter_mut() {
+                        *v /= counts[c] as f64;
+                    }
+                }
+
+                let next = Matrix2d::from_vec_owned(sums, k, d);
+                let shift = next.subtract(&centroids).unwrap()
+                    .get_matrix().iter().map(|v| v * v).sum::<f64>();
+                centroids = next;
+                if shift <= self.tolerance {
+                    break;
+                }
+            }
+
+            let (labels, closest) = assign(&squared_distances(x, &centroids));
+            let inertia = closest.iter().sum::<f64>();
+            let improves = match best {
+                Some((_, _, best_inertia, _)) => inertia < best_inertia,
+                None => true,
+            };
+            if improves {
+                best = Some((centroids, labels, inertia, n_iter));
+            }
+        }
+
+        let (centroids, labels, inertia, n_iter) = best.unwrap();
+        self.centroids = Some(centroids);
+        self.labels = labels;
+        self.inertia = inertia;
+        self.n_iter = n_iter;
+        Some(())
+    }
+
+    // index of the closest centroid for every row of `x`
+    pub fn predict(&self, x: &Matrix2d) -> Option<Vec<usize>> {
+        let centroids = self.centroids.as_ref()?;
+        if x.get_cols() != centroids.get_cols() {
+            return None;
+        }
+        Some(assign(&squared_distances(x, centroids)).0)
+    }
+
+    pub fn get_centroids(&self) -> Option<&Matrix2d> {
+        self.centroids.as_ref()
+    }
+
+    // cluster of every training row from the last fit
+    pub fn get_labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    // sum of squared distances from each training row to its centroid
+    pub fn get_inertia(&self) -> f64 {
+        self.inertia
+    }
+
+    pub fn get_n_iter(&self) -> usize {
+        self.n_iter
+    }
+}
@@ -0,0 +1,155 @@
+use Matrix2d;
+use io::invalid;
+
+use flate2::read::MultiGzDecoder;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+// opens `path`, transparently decompressing it if it starts with the gzip magic
+fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let gzipped = file.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    Ok(if gzipped {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(file)
+    })
+}
+
+// Parses an IDX file, the format MNIST and Fashion-MNIST ship in. The first
+// dimension becomes the rows and the rest are flattened into the columns,
+// so 60000 x 28 x 28 images give a 60000 x 784 matrix and a label file a
+// single column. Values keep their stored scale.
+pub fn read_idx<R: Read>(reader: R) -> io::Result<Matrix2d> {
+    let mut reader = BufReader::new(reader);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic[0] != 0 || magic[1] != 0 || magic[3] == 0 {
+        return Err(invalid("not an IDX file"));
+    }
+
+    let item_size = match magic[2] {
+        0x08 | 0x09 => 1,
+        0x0B => 2,
+        0x0C | 0x0D => 4,
+        0x0E => 8,
+        t => return Err(invalid(&format!("unknown IDX type 0x{:02x}", t))),
+    };
+
+    let mut dims = Vec::with_capacity(magic[3] as usize);
+    for _ in 0..magic[3] {
+        let mut dim = [0u8; 4];
+        reader.read_exact(&mut dim)?;
+        dims.push(u32::from_be_bytes(dim) as usize);
+    }
+    let n_rows = dims[0];
+    let n_cols = dims[1..].iter().try_fold(1usize, |n, &d| n.checked_mul(d))
+        .ok_or_else(|| invalid("IDX dimensions are too large"))?;
+    if n_rows == 0 || n_cols == 0 {
+        return Err(invalid("IDX file holds no data"));
+    }
+    let len = n_rows.checked_mul(n_cols).and_then(|n| n.checked_mul(item_size))
+        .ok_or_else(|| invalid("IDX dimensions are too large"))?;
+
+    // read through `take` rather than allocating whatever the header claims
+    let mut data = Vec::new();
+    if reader.take(len as u64).read_to_end(&mut data)? != len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "IDX data is truncated"));
+    }
+    let matrix = data.chunks(item_size)
+        .map(|b| match magic[2] {
+            0x08 => b[0] as f64,
+            0x09 => b[0] as i8 as f64,
+            0x0B => i16::from_be_bytes([b[0], b[1]]) as f64,
+            0x0C => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            0x0D => f32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            _ => f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]),
+        })
+        .collect::<Vec<f64>>();
+    Ok(Matrix2d::reshape_from_vec(&matrix, n_rows, n_cols).unwrap())
+}
+
+// `read_idx` on a local, optionally gzipped, file
+pub fn load_idx<P: AsRef<Path>>(path: P) -> io::Result<Matrix2d> {
+    read_idx(open(path)?)
+}
+
+// images as rows plus the n x 1 labels, e.g. from `train-images-idx3-ubyte.gz`
+// and `train-labels-idx1-ubyte.gz`
+pub fn load_mnist<P: AsRef<Path>, Q: AsRef<Path>>(images: P, labels: Q) -> io::Result<(Matrix2d, Matrix2d)> {
+    let x = load_idx(images)?;
+    let y = load_idx(labels)?;
+    if x.get_rows() != y.get_rows() || y.get_cols() != 1 {
+        return Err(invalid("images and labels don't line up"));
+    }
+    Ok((x, y))
+}
+
+// the widest `read_libsvm` will infer, pass `n_features` to go past it
+pub const MAX_INFERRED_FEATURES: usize = 1 << 24;
+
+// Parses LIBSVM/SVMlight text, `<label> <index>:<value> ...` per line with
+// 1-based indices, into dense features and an n x 1 label column. `qid:`
+// pairs and `#` comments are ignored. The width is the largest index seen
+// unless `n_features` is given, in which case larger indices are an error.
+// Without it indices above `MAX_INFERRED_FEATURES` are rejected too, so a
+// stray huge index can't blow up the dense allocation.
+pub fn read_libsvm<R: Read>(reader: R, n_features: Option<usize>) -> io::Result<(Matrix2d, Matrix2d)> {
+    let mut labels = Vec::new();
+    let mut rows = Vec::new();
+    let mut width = 0;
+
+    for (idx, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let err = |msg: &str| invalid(&format!("line {}: {}", idx + 1, msg));
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let label = tokens.next().unwrap();
+        labels.push(label.parse::<f64>().map_err(|_| err(&format!("bad label {:?}", label)))?);
+
+        let mut row = Vec::new();
+        for token in tokens {
+            let mut parts = token.splitn(2, ':');
+            let (key, value) = (parts.next().unwrap(), parts.next());
+            if key == "qid" {
+                continue;
+            }
+            let index = key.parse::<usize>().ok().filter(|&i| i >= 1)
+                .ok_or_else(|| err(&format!("bad feature index {:?}", key)))?;
+            let value = value.and_then(|v| v.parse::<f64>().ok())
+                .ok_or_else(|| err(&format!("bad value for feature {}", index)))?;
+            if index > n_features.unwrap_or(MAX_INFERRED_FEATURES) {
+                return Err(err(&format!("feature {} is out of range", index)));
+            }
+            width = width.max(index);
+            row.push((index - 1, value));
+        }
+        rows.push(row);
+    }
+
+    if rows.is_empty() {
+        return Err(invalid("no samples to read"));
+    }
+
+    let n_cols = n_features.unwrap_or(width).max(1);
+    let len = rows.len().checked_mul(n_cols).ok_or_else(|| invalid("too many features"))?;
+    let mut matrix = vec![0.; len];
+    for (r, row) in rows.iter().enumerate() {
+        for &(c, v) in row.iter() {
+            matrix[r * n_cols + c] = v;
+        }
+    }
+    Ok((Matrix2d::reshape_from_vec(&matrix, rows.len(), n_cols).unwrap(),
+        Matrix2d::reshape_from_vec(&labels, labels.len(), 1).unwrap()))
+}
+
+// `read_libsvm` on a local, optionally gzipped, file
+pub fn load_libsvm<P: AsRef<Path>>(path: P, n_features: Option<usize>) -> io::Result<(Matrix2d, Matrix2d)> {
+    read_libsvm(open(path)?, n_features)
+}
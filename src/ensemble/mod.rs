@@ -0,0 +1,173 @@
+use Matrix2d;
+use tree::{Criterion, Dataset, Tree, TreeParams, encode_labels, argmax_labels};
+use utils::flatten;
+
+use rand::distributions::{IndependentSample, Range};
+use rand::{SeedableRng, StdRng};
+use rayon;
+
+// n row indices drawn with replacement, the sampling `Matrix2d::shuffle`
+// does, but seeded per tree and without copying the data
+pub fn bootstrap_indices(n: usize, rng: &mut StdRng) -> Vec<usize> {
+    let sample = Range::new(0, n);
+    (0..n).map(|_| sample.ind_sample(rng)).collect()
+}
+
+// trains trees `lo..hi`, splitting the range across the rayon pool
+fn train_trees(data: &Dataset, params: &TreeParams, seed: &[usize], lo: usize, hi: usize) -> Vec<Tree> {
+    if hi - lo == 1 {
+        let mut tree_seed = seed.to_vec();
+        tree_seed.push(lo);
+        let mut rng: StdRng = StdRng::from_seed(&tree_seed[..]);
+        let mut samples = bootstrap_indices(data.y.len(), &mut rng);
+        return vec![Tree::fit(data, &mut samples, params, &mut rng)];
+    }
+
+    let mid = lo + (hi - lo) / 2;
+    let (mut left, right) = rayon::join(|| train_trees(data, params, seed, lo, mid),
+                                        || train_trees(data, params, seed, mid, hi));
+    left.extend(right);
+    left
+}
+
+// mean of the per-tree predictions
+fn average(trees: &[Tree], x: &Matrix2d, n_outputs: usize) -> Option<Matrix2d> {
+    let mut sum = trees[0].predict(x, n_outputs)?;
+    for tree in trees[1..].iter() {
+        sum = sum.addition(&tree.predict(x, n_outputs)?).unwrap();
+    }
+    Some(sum.scale(1. / trees.len() as f64))
+}
+
+pub struct RandomForestClassifier {
+    n_trees: usize,
+    params: TreeParams,
+    seed: Vec<usize>,
+    classes: Vec<f64>,
+    trees: Vec<Tree>,
+}
+
+impl RandomForestClassifier {
+    pub fn new(n_trees: usize, seed: &[usize]) -> RandomForestClassifier {
+        RandomForestClassifier::with_params(n_trees, Criterion::Gini, None, 2, 1, None, seed)
+    }
+
+    // `max_features` of None considers sqrt(n_features) columns per split
+    pub fn with_params(n_trees: usize, criterion: Criterion, max_depth: Option<usize>,
+                       min_samples_split: usize, min_samples_leaf: usize,
+                       max_features: Option<usize>, seed: &[usize]) -> RandomForestClassifier {
+        RandomForestClassifier {
+            n_trees: n_trees.max(1),
+            params: TreeParams {
+                criterion,
+                max_depth,
+                min_samples_split,
+                min_samples_leaf,
+                max_features,
+            },
+            seed: seed.to_vec(),
+            classes: Vec::new(),
+            trees: Vec::new(),
+        }
+    }
+
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() == 0 ||
+           self.params.criterion == Criterion::Mse {
+            return None;
+        }
+
+        let (classes, encoded) = encode_labels(y)?;
+        let features = flatten(x);
+        let data = Dataset { x: &features, n_features: x.get_cols(), y: &encoded, n_classes: classes.len() };
+
+        let mut params = self.params;
+        if params.max_features.is_none() {
+            params.max_features = Some((x.get_cols() as f64).sqrt().round() as usize);
+        }
+
+        self.trees = train_trees(&data, &params, &self.seed, 0, self.n_trees);
+        self.classes = classes;
+        Some(())
+    }
+
+    // mean of the trees' leaf class frequencies
+    pub fn predict_proba(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        if self.trees.is_empty() {
+            return None;
+        }
+        average(&self.trees, x, self.classes.len())
+    }
+
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        Some(argmax_labels(&self.predict_proba(x)?, &self.classes))
+    }
+
+    pub fn get_classes(&self) -> &[f64] {
+        &self.classes
+    }
+
+    pub fn get_n_trees(&self) -> usize {
+        self.trees.len()
+    }
+}
+
+pub struct RandomForestRegressor {
+    n_trees: usize,
+    params: TreeParams,
+    seed: Vec<usize>,
+    trees: Vec<Tree>,
+}
+
+impl RandomForestRegressor {
+    pub fn new(n_trees: usize, seed: &[usize]) -> RandomForestRegressor {
+        RandomForestRegressor::with_params(n_trees, None, 2, 1, None, seed)
+    }
+
+    // `max_features` of None considers n_features / 3 columns per split
+    pub fn with_params(n_trees: usize, max_depth: Option<usize>, min_samples_split: usize,
+                       min_samples_leaf: usize, max_features: Option<usize>,
+                       seed: &[usize]) -> RandomForestRegressor {
+        RandomForestRegressor {
+            n_trees: n_trees.max(1),
+            params: TreeParams {
+                criterion: Criterion::Mse,
+                max_depth,
+                min_samples_split,
+                min_samples_leaf,
+                max_features,
+            },
+            seed: seed.to_vec(),
+            trees: Vec::new(),
+        }
+    }
+
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() == 0 {
+            return None;
+        }
+
+        let features = flatten(x);
+        let targets = y.get_col(0).unwrap();
+        let data = Dataset { x: &features, n_features: x.get_cols(), y: &targets, n_classes: 0 };
+
+        let mut params = self.params;
+        if params.max_features.is_none() {
+            params.max_features = Some(x.get_cols() / 3);
+        }
+
+        self.trees = train_trees(&data, &params, &self.seed, 0, self.n_trees);
+        Some(())
+    }
+
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        if self.trees.is_empty() {
+            return None;
+        }
+        average(&self.trees, x, 1)
+    }
+
+    pub fn get_n_trees(&self) -> usize {
+        self.trees.len()
+    }
+}
@@ -0,0 +1,264 @@
+use Matrix2d;
+use utils::{par_chunk_size, par_fill};
+
+use std::sync::Arc;
+
+// the operations the built-in combinators store in their nodes
+pub type BinOp = fn(f64, f64) -> f64;
+pub type BoxedFn = Box<dyn Fn(f64) -> f64 + Sync>;
+
+// Lazy elementwise expressions. Building one only records the operations;
+// `eval` and friends then compute every element in a single pass, so
+//
+//     a.lazy().mult(&b).addition(&c).apply_fn(sigmoid).eval()
+//
+// allocates just the result instead of three intermediates. Shapes are
+// only checked on evaluation, which returns None if they don't line up.
+pub trait Expr: Sync {
+    // None if the operands of some node disagree
+    fn shape(&self) -> Option<(usize, usize)>;
+
+    // The strides shared by every matrix in the expression, or None if they
+    // differ. When shared, elements can be addressed by buffer index.
+    fn strides(&self) -> Option<(usize, usize)>;
+
+    fn at(&self, row: usize, col: usize) -> f64;
+
+    // element at buffer index `i`, only valid when `strides` is Some
+    fn at_index(&self, i: usize) -> f64;
+
+    fn addition<B: IntoExpr>(self, b: B) -> Zip<Self, B::Expr, BinOp>
+        where Self: Sized
+    {
+        Zip { a: self, b: b.into_expr(), f: |x, y| x + y }
+    }
+
+    fn subtract<B: IntoExpr>(self, b: B) -> Zip<Self, B::Expr, BinOp>
+        where Self: Sized
+    {
+        Zip { a: self, b: b.into_expr(), f: |x, y| x - y }
+    }
+
+    fn mult<B: IntoExpr>(self, b: B) -> Zip<Self, B::Expr, BinOp>
+        where Self: Sized
+    {
+        Zip { a: self, b: b.into_expr(), f: |x, y| x * y }
+    }
+
+    fn zip_fn<B: IntoExpr, F>(self, b: B, f: F) -> Zip<Self, B::Expr, F>
+        where Self: Sized, F: Fn(f64, f64) -> f64 + Sync
+    {
+        Zip { a: self, b: b.into_expr(), f }
+    }
+
+    fn apply_fn<F>(self, f: F) -> Map<Self, F>
+        where Self: Sized, F: Fn(f64) -> f64 + Sync
+    {
+        Map { a: self, f }
+    }
+
+    fn scale(self, k: f64) -> Map<Self, BoxedFn>
+        where Self: Sized
+    {
+        Map { a: self, f: Box::new(move |x| x * k) }
+    }
+
+    fn eval(&self) -> Option<Matrix2d> {
+        let (n_rows, n_cols) = self.shape()?;
+        Some(match self.strides() {
+            Some((rs, cs)) => Matrix2d {
+                n_rows,
+                n_cols,
+                rs,
+                cs,
+                matrix: Arc::new((0..n_rows * n_cols).map(|i| self.at_index(i)).collect()),
+            },
+            None => Matrix2d {
+                n_rows,
+                n_cols,
+                rs: n_cols,
+                cs: 1,
+                matrix: Arc::new((0..n_rows * n_cols).map(|i| self.at(i / n_cols, i % n_cols)).collect()),
+            },
+        })
+    }
+
+    // `eval` with the pass split across the rayon pool
+    fn par_eval(&self) -> Option<Matrix2d> {
+        let (n_rows, n_cols) = self.shape()?;
+        let len = n_rows * n_cols;
+        let mut matrix = vec![0.; len];
+        let (rs, cs) = match self.strides() {
+            Some(strides) => {
+                par_fill(&mut matrix, 0, par_chunk_size(len), &|i| self.at_index(i));
+                strides
+            }
+            None => {
+                par_fill(&mut matrix, 0, par_chunk_size(len), &|i| self.at(i / n_cols, i % n_cols));
+                (n_cols, 1)
+            }
+        };
+        Some(Matrix2d { n_rows, n_cols, rs, cs, matrix: Arc::new(matrix) })
+    }
+
+    // Writes the result into `dst`, in its own layout, without allocating.
+    // None, leaving `dst` untouched, if the shapes don't match.
+    fn eval_into(&self, dst: &mut Matrix2d) -> Option<()> {
+        if self.shape()? != (dst.n_rows, dst.n_cols) {
+            return None;
+        }
+        let (n_rows, n_cols, rs, cs) = (dst.n_rows, dst.n_cols, dst.rs, dst.cs);
+        let matrix = dst.get_matrix_for_overwrite();
+        if self.strides() == Some((rs, cs)) {
+            for (i, d) in matrix.iter_mut().enumerate() {
+                *d = self.at_index(i);
+            }
+        } else {
+            for row in 0..n_rows {
+                for col in 0..n_cols {
+                    matrix[row * rs + col * cs] = self.at(row, col);
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn par_eval_into(&self, dst: &mut Matrix2d) -> Option<()> {
+        if self.shape()? != (dst.n_rows, dst.n_cols) {
+            return None;
+        }
+        let (n_rows, n_cols, rs, cs) = (dst.n_rows, dst.n_cols, dst.rs, dst.cs);
+        let matrix = dst.get_matrix_for_overwrite();
+        let chunk_size = par_chunk_size(matrix.len());
+        if self.strides() == Some((rs, cs)) {
+            par_fill(matrix, 0, chunk_size, &|i| self.at_index(i));
+        } else {
+            // buffer index back to (row, col), from dst's strides; a single
+            // row or column can have either stride set to 1
+            let position = move |i: usize| {
+                if n_rows == 1 {
+                    (0, i / cs)
+                } else if n_cols == 1 {
+                    (i / rs, 0)
+                } else if rs > cs {
+                    (i / rs, i % rs / cs)
+                } else {
+                    (i % cs / rs, i / cs)
+                }
+            };
+            par_fill(matrix, 0, chunk_size, &|i| {
+                let (row, col) = position(i);
+                self.at(row, col)
+            });
+        }
+        Some(())
+    }
+}
+
+// anything that can be an operand: expressions and borrowed matrices
+pub trait IntoExpr {
+    type Expr: Expr;
+
+    fn into_expr(self) -> Self::Expr;
+}
+
+impl<E: Expr> IntoExpr for E {
+    type Expr = E;
+
+    fn into_expr(self) -> E {
+        self
+    }
+}
+
+impl<'a> IntoExpr for &'a Matrix2d {
+    type Expr = Leaf<'a>;
+
+    fn into_expr(self) -> Leaf<'a> {
+        self.lazy()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Leaf<'a> {
+    m: &'a Matrix2d,
+}
+
+impl<'a> Expr for Leaf<'a> {
+    fn shape(&self) -> Option<(usize, usize)> {
+        Some((self.m.n_rows, self.m.n_cols))
+    }
+
+    fn strides(&self) -> Option<(usize, usize)> {
+        Some((self.m.rs, self.m.cs))
+    }
+
+    fn at(&self, row: usize, col: usize) -> f64 {
+        self.m.matrix[row * self.m.rs + col * self.m.cs]
+    }
+
+    fn at_index(&self, i: usize) -> f64 {
+        self.m.matrix[i]
+    }
+}
+
+pub struct Zip<A, B, F> {
+    a: A,
+    b: B,
+    f: F,
+}
+
+impl<A: Expr, B: Expr, F: Fn(f64, f64) -> f64 + Sync> Expr for Zip<A, B, F> {
+    fn shape(&self) -> Option<(usize, usize)> {
+        let shape = self.a.shape()?;
+        if shape != self.b.shape()? {
+            return None;
+        }
+        Some(shape)
+    }
+
+    fn strides(&self) -> Option<(usize, usize)> {
+        let strides = self.a.strides()?;
+        if strides != self.b.strides()? {
+            return None;
+        }
+        Some(strides)
+    }
+
+    fn at(&self, row: usize, col: usize) -> f64 {
+        (self.f)(self.a.at(row, col), self.b.at(row, col))
+    }
+
+    fn at_index(&self, i: usize) -> f64 {
+        (self.f)(self.a.at_index(i), self.b.at_index(i))
+    }
+}
+
+pub struct Map<A, F> {
+    a: A,
+    f: F,
+}
+
+impl<A: Expr, F: Fn(f64) -> f64 + Sync> Expr for Map<A, F> {
+    fn shape(&self) -> Option<(usize, usize)> {
+        self.a.shape()
+    }
+
+    fn strides(&self) -> Option<(usize, usize)> {
+        self.a.strides()
+    }
+
+    fn at(&self, row: usize, col: usize) -> f64 {
+        (self.f)(self.a.at(row, col))
+    }
+
+    fn at_index(&self, i: usize) -> f64 {
+        (self.f)(self.a.at_index(i))
+    }
+}
+
+impl Matrix2d {
+    // starts a lazy expression, see `Expr`
+    pub fn lazy(&self) -> Leaf<'_> {
+        Leaf { m: self }
+    }
+}
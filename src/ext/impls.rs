@@ -7,9 +7,14 @@ use std::ops::{Neg, Sub};
 
 impl PartialEq for Matrix2d {
     fn eq(&self, other: &Matrix2d) -> bool {
-        self.n_cols == other.get_cols() &&
-        self.n_rows == other.get_rows() &&
-        &self.matrix == other.get_matrix()
+        if self.n_cols != other.get_cols() || self.n_rows != other.get_rows() {
+            return false;
+        }
+        // the same elements may be stored in different orders
+        if self.rs == other.rs && self.cs == other.cs {
+            return self.get_matrix() == other.get_matrix();
+        }
+        (0..self.n_rows).all(|row| self.get_row(row) == other.get_row(row))
     }
 }
 
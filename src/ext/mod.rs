@@ -1,2 +1,4 @@
 pub mod impls;
 pub mod traits;
+#[cfg(feature = "serde")]
+mod serde_impls;
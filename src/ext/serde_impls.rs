@@ -0,0 +1,47 @@
+use Matrix2d;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// Wire format: the shape plus the elements in row-major order, whatever the
+// strides of the matrix being written.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Matrix2d")]
+struct Repr<'a> {
+    n_rows: usize,
+    n_cols: usize,
+    data: Cow<'a, [f64]>,
+}
+
+impl Serialize for Matrix2d {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = if self.cs == 1 && self.rs == self.n_cols {
+            Cow::Borrowed(&self.matrix[..])
+        } else {
+            Cow::Owned((0..self.n_rows)
+                .flat_map(|row| (0..self.n_cols).map(move |col| self.matrix[row * self.rs + col * self.cs]))
+                .collect())
+        };
+        Repr { n_rows: self.n_rows, n_cols: self.n_cols, data }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Matrix2d {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Matrix2d, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+        if repr.n_rows.checked_mul(repr.n_cols) != Some(repr.data.len()) {
+            return Err(D::Error::custom(format!("{} elements don't fill a {} x {} matrix",
+                                                repr.data.len(), repr.n_rows, repr.n_cols)));
+        }
+        Ok(Matrix2d {
+            n_rows: repr.n_rows,
+            n_cols: repr.n_cols,
+            rs: repr.n_cols,
+            cs: 1,
+            matrix: Arc::new(repr.data.into_owned()),
+        })
+    }
+}
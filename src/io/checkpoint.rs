@@ -0,0 +1,355 @@
+use Matrix2d;
+use super::invalid;
+
+use flate2::Crc;
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+// Layout, all integers little-endian:
+//
+//   header   MAGIC, version: u32, reserved: u32
+//   entries  each matrix's raw buffer, starting on an 8 byte boundary
+//   index    count: u32, then per entry: name_len: u16, name, dtype: u8,
+//            n_rows, n_cols, rs, cs, len (elements), offset: u64, crc: u32
+//   trailer  index offset: u64, index crc: u32, reserved: u32, MAGIC
+//
+// Entries keep their strides, so a transposed matrix reads back transposed.
+// The index goes last so matrices can be streamed out one at a time.
+const MAGIC: &[u8; 8] = b"NUMRUST\x00";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 16;
+const TRAILER_LEN: usize = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dtype {
+    F64,
+    // halves the file at the cost of precision
+    F32,
+}
+
+impl Dtype {
+    fn code(&self) -> u8 {
+        match *self {
+            Dtype::F64 => 1,
+            Dtype::F32 => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Dtype> {
+        match code {
+            1 => Some(Dtype::F64),
+            2 => Some(Dtype::F32),
+            _ => None,
+        }
+    }
+
+    fn item_size(&self) -> usize {
+        match *self {
+            Dtype::F64 => 8,
+            Dtype::F32 => 4,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    name: String,
+    dtype: Dtype,
+    n_rows: usize,
+    n_cols: usize,
+    rs: usize,
+    cs: usize,
+    len: usize,
+    offset: usize,
+    crc: u32,
+}
+
+impl Entry {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_dtype(&self) -> Dtype {
+        self.dtype
+    }
+
+    pub fn get_shape(&self) -> (usize, usize) {
+        (self.n_rows, self.n_cols)
+    }
+
+    pub fn get_strides(&self) -> (usize, usize) {
+        (self.rs, self.cs)
+    }
+
+    fn byte_len(&self) -> usize {
+        self.len * self.dtype.item_size()
+    }
+}
+
+// Streams matrices into a checkpoint; nothing is readable until `finish`
+// writes the index.
+pub struct CheckpointWriter<W: Write> {
+    writer: BufWriter<W>,
+    offset: usize,
+    entries: Vec<Entry>,
+}
+
+impl<W: Write> CheckpointWriter<W> {
+    pub fn new(writer: W) -> io::Result<CheckpointWriter<W>> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        Ok(CheckpointWriter { writer, offset: HEADER_LEN, entries: Vec::new() })
+    }
+
+    pub fn write(&mut self, name: &str, m: &Matrix2d) -> io::Result<()> {
+        self.write_as(name, m, Dtype::F64)
+    }
+
+    pub fn write_as(&mut self, name: &str, m: &Matrix2d, dtype: Dtype) -> io::Result<()> {
+        if name.len() > u16::MAX as usize || self.entries.iter().any(|e| e.name == name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("entry name {:?} is too long or already used", name)));
+        }
+
+        let padding = (8 - self.offset % 8) % 8;
+        self.writer.write_all(&[0u8; 8][..padding])?;
+        self.offset += padding;
+
+        let mut crc = Crc::new();
+        let mut buf = Vec::with_capacity(4096);
+        for chunk in m.matrix.chunks(512) {
+            buf.clear();
+            for &v in chunk.iter() {
+                match dtype {
+                    Dtype::F64 => buf.extend_from_slice(&v.to_le_bytes()),
+                    Dtype::F32 => buf.extend_from_slice(&(v as f32).to_le_bytes()),
+                }
+            }
+            crc.update(&buf);
+            self.writer.write_all(&buf)?;
+        }
+
+        let entry = Entry {
+            name: name.to_string(),
+            dtype,
+            n_rows: m.n_rows,
+            n_cols: m.n_cols,
+            rs: m.rs,
+            cs: m.cs,
+            len: m.matrix.len(),
+            offset: self.offset,
+            crc: crc.sum(),
+        };
+        self.offset += entry.byte_len();
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    // writes the index and trailer, handing back the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut index = Vec::new();
+        index.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for e in self.entries.iter() {
+            index.extend_from_slice(&(e.name.len() as u16).to_le_bytes());
+            index.extend_from_slice(e.name.as_bytes());
+            index.push(e.dtype.code());
+            for &v in [e.n_rows, e.n_cols, e.rs, e.cs, e.len, e.offset].iter() {
+                index.extend_from_slice(&(v as u64).to_le_bytes());
+            }
+            index.extend_from_slice(&e.crc.to_le_bytes());
+        }
+        let mut crc = Crc::new();
+        crc.update(&index);
+
+        self.writer.write_all(&index)?;
+        self.writer.write_all(&(self.offset as u64).to_le_bytes())?;
+        self.writer.write_all(&crc.sum().to_le_bytes())?;
+        self.writer.write_all(&0u32.to_le_bytes())?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+// writes `entries` to a new file at `path`
+pub fn save<P: AsRef<Path>>(path: P, entries: &[(&str, &Matrix2d)]) -> io::Result<()> {
+    let mut writer = CheckpointWriter::new(File::create(path)?)?;
+    for &(name, m) in entries.iter() {
+        writer.write(name, m)?;
+    }
+    writer.finish()?.sync_all()
+}
+
+// Cursor over the index bytes, failing instead of panicking on truncation.
+struct IndexReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> IndexReader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid("checkpoint index is truncated"));
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u64(&mut self) -> io::Result<usize> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(b) as usize)
+    }
+}
+
+fn u32_at(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+// A checkpoint opened for reading. Files are memory mapped, so opening only
+// touches the header and index and each `get` only the pages of its entry.
+pub struct Checkpoint {
+    bytes: Box<dyn AsRef<[u8]> + Send + Sync>,
+    entries: Vec<Entry>,
+}
+
+impl Checkpoint {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Checkpoint> {
+        let file = File::open(path)?;
+        // the usual mmap caveat applies: the file must not be modified while open
+        let mmap = unsafe { Mmap::map(&file)? };
+        Checkpoint::from_storage(Box::new(mmap))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Checkpoint> {
+        Checkpoint::from_storage(Box::new(bytes))
+    }
+
+    fn from_storage(bytes: Box<dyn AsRef<[u8]> + Send + Sync>) -> io::Result<Checkpoint> {
+        let entries = {
+            let b = (*bytes).as_ref();
+            if b.len() < HEADER_LEN + TRAILER_LEN || &b[..8] != MAGIC || &b[b.len() - 8..] != MAGIC {
+                return Err(invalid("not a checkpoint file"));
+            }
+            let version = u32_at(b, 8);
+            if version != VERSION {
+                return Err(invalid(&format!("unsupported checkpoint version {}", version)));
+            }
+
+            let trailer = &b[b.len() - TRAILER_LEN..];
+            let mut offset = [0u8; 8];
+            offset.copy_from_slice(&trailer[..8]);
+            let index_start = u64::from_le_bytes(offset) as usize;
+            let index_end = b.len() - TRAILER_LEN;
+            if index_start < HEADER_LEN || index_start > index_end {
+                return Err(invalid("checkpoint index offset is out of range"));
+            }
+
+            let index = &b[index_start..index_end];
+            let mut crc = Crc::new();
+            crc.update(index);
+            if crc.sum() != u32_at(trailer, 8) {
+                return Err(invalid("checkpoint index checksum mismatch"));
+            }
+            parse_index(index, index_start)?
+        };
+        Ok(Checkpoint { bytes, entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // entries in the order they were written
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.get_name()).collect()
+    }
+
+    // decodes and checksums the named matrix
+    pub fn get(&self, name: &str) -> io::Result<Matrix2d> {
+        let entry = self.entries.iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no entry {:?}", name)))?;
+        let data = &(*self.bytes).as_ref()[entry.offset..entry.offset + entry.byte_len()];
+
+        let mut crc = Crc::new();
+        crc.update(data);
+        if crc.sum() != entry.crc {
+            return Err(invalid(&format!("checksum mismatch in entry {:?}", name)));
+        }
+
+        let matrix = match entry.dtype {
+            Dtype::F64 => data.chunks(8)
+                .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+                .collect(),
+            Dtype::F32 => data.chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+                .collect(),
+        };
+        Ok(Matrix2d {
+            n_rows: entry.n_rows,
+            n_cols: entry.n_cols,
+            rs: entry.rs,
+            cs: entry.cs,
+            matrix: Arc::new(matrix),
+        })
+    }
+}
+
+// `data_end` is where the index starts; every entry has to lie before it and
+// index only into its own buffer
+fn parse_index(index: &[u8], data_end: usize) -> io::Result<Vec<Entry>> {
+    let mut r = IndexReader { bytes: index };
+    let count = u32_at(r.take(4)?, 0) as usize;
+
+    let mut entries = Vec::with_capacity(count.min(index.len()));
+    for _ in 0..count {
+        let name_len = r.take(2)?;
+        let name_len = u16::from_le_bytes([name_len[0], name_len[1]]) as usize;
+        let name = String::from_utf8(r.take(name_len)?.to_vec())
+            .map_err(|_| invalid("checkpoint entry name isn't utf-8"))?;
+        let dtype = Dtype::from_code(r.take(1)?[0])
+            .ok_or_else(|| invalid(&format!("unknown dtype in entry {:?}", name)))?;
+
+        let entry = Entry {
+            name,
+            dtype,
+            n_rows: r.u64()?,
+            n_cols: r.u64()?,
+            rs: r.u64()?,
+            cs: r.u64()?,
+            len: r.u64()?,
+            offset: r.u64()?,
+            crc: u32_at(r.take(4)?, 0),
+        };
+
+        let fits = entry.len.checked_mul(dtype.item_size())
+            .and_then(|n| n.checked_add(entry.offset))
+            .is_some_and(|end| entry.offset >= HEADER_LEN && end <= data_end);
+        let last = if entry.n_rows == 0 || entry.n_cols == 0 {
+            Some(0)
+        } else {
+            (entry.n_rows - 1).checked_mul(entry.rs)
+                .and_then(|a| (entry.n_cols - 1).checked_mul(entry.cs).and_then(|b| a.checked_add(b)))
+                .and_then(|i| i.checked_add(1))
+        };
+        if !fits || last.is_none_or(|l| l > entry.len) {
+            return Err(invalid(&format!("entry {:?} is out of bounds", entry.name)));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
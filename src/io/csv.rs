@@ -0,0 +1,165 @@
+use Matrix2d;
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
+
+// what to do with an empty field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Missing {
+    Error,
+    NaN,
+    Fill(f64),
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    // skip the first line when reading
+    pub has_header: bool,
+    // columns to keep, in the given order; None keeps all of them
+    pub columns: Option<Vec<usize>>,
+    pub missing: Missing,
+    // digits after the decimal point when writing; None writes the shortest
+    // representation that round-trips
+    pub precision: Option<usize>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: b',',
+            has_header: false,
+            columns: None,
+            missing: Missing::Error,
+            precision: None,
+        }
+    }
+}
+
+fn invalid(line: usize, msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+// splits a line on `delimiter`, honouring double quoted fields with `""` escapes
+fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(field);
+            field = String::new();
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn parse_field(field: &str, missing: Missing, line: usize, col: usize) -> io::Result<f64> {
+    let field = field.trim();
+    if field.is_empty() {
+        return match missing {
+            Missing::Error => Err(invalid(line, format!("missing value in column {}", col))),
+            Missing::NaN => Ok(f64::NAN),
+            Missing::Fill(v) => Ok(v),
+        };
+    }
+    field.parse::<f64>()
+        .map_err(|_| invalid(line, format!("can't parse {:?} in column {} as a number", field, col)))
+}
+
+impl Matrix2d {
+    // Reads delimited numeric text line by line straight into the matrix
+    // buffer, so the file is never held as nested vecs. Blank lines are
+    // skipped; every other line must have the same number of fields.
+    pub fn from_csv<R: Read>(reader: R, options: &CsvOptions) -> io::Result<Matrix2d> {
+        let delimiter = options.delimiter as char;
+        let mut matrix = Vec::new();
+        let mut n_fields = None;
+        let mut n_rows = 0;
+
+        for (idx, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line_no = idx + 1;
+            if (idx == 0 && options.has_header) || line.trim().is_empty() {
+                continue;
+            }
+
+            let fields = split_fields(line.trim_end_matches('\r'), delimiter);
+            match n_fields {
+                None => n_fields = Some(fields.len()),
+                Some(n) if n != fields.len() => {
+                    return Err(invalid(line_no, format!("expected {} fields, found {}", n, fields.len())));
+                }
+                _ => {}
+            }
+
+            match options.columns {
+                Some(ref columns) => {
+                    for &col in columns.iter() {
+                        let field = fields.get(col)
+                            .ok_or_else(|| invalid(line_no, format!("no column {}", col)))?;
+                        matrix.push(parse_field(field, options.missing, line_no, col)?);
+                    }
+                }
+                None => {
+                    for (col, field) in fields.iter().enumerate() {
+                        matrix.push(parse_field(field, options.missing, line_no, col)?);
+                    }
+                }
+            }
+            n_rows += 1;
+        }
+
+        if n_rows == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no rows to read"));
+        }
+
+        let n_cols = matrix.len() / n_rows;
+        Ok(Matrix2d {
+            n_rows,
+            n_cols,
+            rs: n_cols,
+            cs: 1,
+            matrix: Arc::new(matrix),
+        })
+    }
+
+    // Writes one line per row; only `delimiter` and `precision` are used.
+    pub fn to_csv<W: Write>(&self, writer: W, options: &CsvOptions) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let delimiter = options.delimiter as char;
+
+        for row in 0..self.n_rows {
+            for col in 0..self.n_cols {
+                if col > 0 {
+                    write!(writer, "{}", delimiter)?;
+                }
+                let v = self.matrix[row * self.rs + col * self.cs];
+                match options.precision {
+                    Some(p) => write!(writer, "{:.*}", p, v)?,
+                    None => write!(writer, "{}", v)?,
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,206 @@
+use Matrix2d;
+use ext::traits::ToMatrix2d;
+use io::npy::{self, Dtype};
+use super::invalid;
+use utils::Layout;
+
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+// A read-only matrix backed by a memory-mapped file, for data that doesn't
+// fit in memory. Element (r, c) lives at byte `offset + (r * rs + c * cs) *
+// item_size` of the mapping, so slicing and transposing are O(1) views that
+// share it; elements are only decoded into owned memory by the methods that
+// return a `Matrix2d` or `Vec`.
+#[derive(Clone)]
+pub struct MmapMatrix {
+    map: Arc<Mmap>,
+    dtype: Dtype,
+    offset: usize,
+    n_rows: usize,
+    n_cols: usize,
+    rs: usize,
+    cs: usize,
+}
+
+fn map_file<P: AsRef<Path>>(path: P) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    // the file must not be modified while mapped
+    unsafe { Mmap::map(&file) }
+}
+
+impl MmapMatrix {
+    // `n_rows x n_cols` elements stored row-major with no header
+    pub fn open_raw<P: AsRef<Path>>(path: P, n_rows: usize, n_cols: usize, dtype: Dtype) -> io::Result<MmapMatrix> {
+        MmapMatrix::from_map(map_file(path)?, 0, dtype, n_rows, n_cols, false)
+    }
+
+    // an .npy file of any dtype `read_npy` supports, in either order
+    pub fn open_npy<P: AsRef<Path>>(path: P) -> io::Result<MmapMatrix> {
+        let map = map_file(path)?;
+        let (header, offset) = npy::read_header(&mut &map[..])?;
+        MmapMatrix::from_map(map, offset, header.dtype, header.n_rows, header.n_cols, header.fortran_order)
+    }
+
+    fn from_map(map: Mmap, offset: usize, dtype: Dtype, n_rows: usize, n_cols: usize,
+                fortran_order: bool) -> io::Result<MmapMatrix> {
+        let needed = n_rows.checked_mul(n_cols)
+            .and_then(|n| n.checked_mul(dtype.item_size()))
+            .and_then(|n| n.checked_add(offset));
+        if needed.is_none_or(|n| n > map.len()) {
+            return Err(invalid("file is too short for the given shape"));
+        }
+        let (rs, cs) = if fortran_order { (1, n_rows) } else { (n_cols, 1) };
+        Ok(MmapMatrix { map: Arc::new(map), dtype, offset, n_rows, n_cols, rs, cs })
+    }
+
+    pub fn get_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn get_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    pub fn get_row_stride(&self) -> usize {
+        self.rs
+    }
+
+    pub fn get_col_stride(&self) -> usize {
+        self.cs
+    }
+
+    fn at(&self, row: usize, col: usize) -> f64 {
+        let size = self.dtype.item_size();
+        let start = self.offset + (row * self.rs + col * self.cs) * size;
+        self.dtype.decode(&self.map[start..start + size])
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        if row >= self.n_rows || col >= self.n_cols {
+            return None;
+        }
+        Some(self.at(row, col))
+    }
+
+    pub fn get_row(&self, n_row: usize) -> Option<Vec<f64>> {
+        if n_row >= self.n_rows {
+            return None;
+        }
+        Some((0..self.n_cols).map(|col| self.at(n_row, col)).collect())
+    }
+
+    pub fn get_col(&self, n_col: usize) -> Option<Vec<f64>> {
+        if n_col >= self.n_cols {
+            return None;
+        }
+        Some((0..self.n_rows).map(|row| self.at(row, n_col)).collect())
+    }
+
+    // view of rows `start..end`
+    pub fn rows(&self, start: usize, end: usize) -> Option<MmapMatrix> {
+        if start > end || end > self.n_rows {
+            return None;
+        }
+        Some(MmapMatrix {
+            offset: self.offset + start * self.rs * self.dtype.item_size(),
+            n_rows: end - start,
+            ..self.clone()
+        })
+    }
+
+    // view of columns `start..end`
+    pub fn cols(&self, start: usize, end: usize) -> Option<MmapMatrix> {
+        if start > end || end > self.n_cols {
+            return None;
+        }
+        Some(MmapMatrix {
+            offset: self.offset + start * self.cs * self.dtype.item_size(),
+            n_cols: end - start,
+            ..self.clone()
+        })
+    }
+
+    pub fn transpose(&self) -> MmapMatrix {
+        MmapMatrix {
+            n_rows: self.n_cols,
+            n_cols: self.n_rows,
+            rs: self.cs,
+            cs: self.rs,
+            ..self.clone()
+        }
+    }
+
+    // Multiplies by an in-memory matrix a block of rows at a time, so only
+    // the block and the result are ever held in memory.
+    pub fn dot(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        if self.n_cols != m.get_rows() {
+            return None;
+        }
+        if self.n_rows == 0 || self.n_cols == 0 || m.get_cols() == 0 {
+            return Some(Matrix2d::new(self.n_rows, m.get_cols()));
+        }
+        let block = (1 << 20) / self.n_cols.max(1) + 1;
+
+        let mut out = Vec::with_capacity(self.n_rows * m.get_cols());
+        let mut start = 0;
+        while start < self.n_rows {
+            let end = (start + block).min(self.n_rows);
+            let product = self.rows(start, end)?.to_matrix_2d()?.dot(m)?;
+            out.extend_from_slice(product.get_matrix());
+            start = end;
+        }
+        Matrix2d::from_vec_with_layout(out, self.n_rows, m.get_cols(), Layout::RowMajor)
+    }
+
+    // Consecutive batches of `batch_size` rows (the last may be smaller),
+    // each copied out only when the iterator reaches it. Unlike
+    // `Matrix2d::mini_batch` nothing is materialized up front.
+    pub fn mini_batch(&self, batch_size: usize) -> MiniBatches {
+        MiniBatches { source: self.clone(), batch_size: batch_size.max(1), next: 0 }
+    }
+}
+
+impl ToMatrix2d for MmapMatrix {
+    // copies the view into an owned, row-major matrix
+    fn to_matrix_2d(&self) -> Option<Matrix2d> {
+        if self.n_rows == 0 || self.n_cols == 0 {
+            return None;
+        }
+        let vec = (0..self.n_rows)
+            .flat_map(|row| (0..self.n_cols).map(move |col| self.at(row, col)))
+            .collect::<Vec<f64>>();
+        Matrix2d::from_vec_with_layout(vec, self.n_rows, self.n_cols, Layout::RowMajor)
+    }
+
+    fn reshape(&self, n_rows: usize, n_cols: usize) -> Option<Matrix2d> {
+        if n_rows * n_cols != self.n_rows * self.n_cols {
+            return None;
+        }
+        self.to_matrix_2d()?.reshape(n_rows, n_cols)
+    }
+}
+
+pub struct MiniBatches {
+    source: MmapMatrix,
+    batch_size: usize,
+    next: usize,
+}
+
+impl Iterator for MiniBatches {
+    type Item = Matrix2d;
+
+    fn next(&mut self) -> Option<Matrix2d> {
+        if self.next >= self.source.n_rows {
+            return None;
+        }
+        let end = (self.next + self.batch_size).min(self.source.n_rows);
+        let batch = self.source.rows(self.next, end)?.to_matrix_2d();
+        self.next = end;
+        batch
+    }
+}
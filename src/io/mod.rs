@@ -0,0 +1,12 @@
+use std::io::{Error, ErrorKind};
+
+pub mod csv;
+pub mod npy;
+pub mod mtx;
+pub mod checkpoint;
+pub mod mmap;
+
+// the error every reader returns for malformed input
+pub(crate) fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.to_string())
+}
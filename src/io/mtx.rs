@@ -0,0 +1,254 @@
+use Matrix2d;
+use sparse::CsrMatrix;
+use super::invalid;
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Real,
+    Integer,
+    // coordinate only: the positions of the non-zeros, all read as 1
+    Pattern,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    General,
+    // only the lower triangle is stored
+    Symmetric,
+    // only the strictly lower triangle is stored, a[j][i] = -a[i][j]
+    SkewSymmetric,
+}
+
+// The most elements `Matrix2d::read_mtx` will densify, and the most rows
+// `CsrMatrix::read_mtx` will allocate row pointers for, so a bogus size line
+// is an error rather than a huge allocation.
+pub const MAX_MTX_ELEMENTS: usize = 1 << 28;
+
+struct Parsed {
+    n_rows: usize,
+    n_cols: usize,
+    // entries after expanding the symmetry
+    triplets: Vec<(usize, usize, f64)>,
+}
+
+fn parse_value(token: Option<&str>, field: Field) -> io::Result<f64> {
+    let token = token.ok_or_else(|| invalid("missing value"))?;
+    match field {
+        Field::Integer => token.parse::<i64>().map(|v| v as f64)
+            .map_err(|_| invalid(&format!("{:?} isn't an integer", token))),
+        _ => token.parse::<f64>().map_err(|_| invalid(&format!("{:?} isn't a number", token))),
+    }
+}
+
+fn parse_index(token: Option<&str>, bound: usize) -> io::Result<usize> {
+    match token.and_then(|t| t.parse::<usize>().ok()) {
+        Some(i) if i >= 1 && i <= bound => Ok(i - 1),
+        _ => Err(invalid("index missing or out of bounds")),
+    }
+}
+
+fn parse<R: Read>(reader: R) -> io::Result<Parsed> {
+    let mut lines = BufReader::new(reader).lines();
+
+    let banner = lines.next().ok_or_else(|| invalid("empty file"))??.to_lowercase();
+    let words = banner.split_whitespace().collect::<Vec<&str>>();
+    if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+        return Err(invalid("not a Matrix Market matrix"));
+    }
+    let coordinate = match words[2] {
+        "coordinate" => true,
+        "array" => false,
+        f => return Err(invalid(&format!("unknown format {}", f))),
+    };
+    let field = match words[3] {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" if coordinate => Field::Pattern,
+        f => return Err(invalid(&format!("unsupported field {}", f))),
+    };
+    let symmetry = match words[4] {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        s => return Err(invalid(&format!("unsupported symmetry {}", s))),
+    };
+
+    // comments and blank lines may appear anywhere after the banner
+    let mut data = lines.filter(|l| {
+        l.as_ref().map(|l| !l.trim().is_empty() && !l.starts_with('%')).unwrap_or(true)
+    });
+
+    let size = data.next().ok_or_else(|| invalid("missing size line"))??;
+    let size = size.split_whitespace()
+        .map(|t| t.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| invalid("bad size line"))?;
+    let (n_rows, n_cols) = match (size.len(), coordinate) {
+        (3, true) | (2, false) => (size[0], size[1]),
+        _ => return Err(invalid("bad size line")),
+    };
+    if symmetry != Symmetry::General && n_rows != n_cols {
+        return Err(invalid("symmetric matrices must be square"));
+    }
+
+    let mut triplets = Vec::new();
+    if coordinate {
+        for _ in 0..size[2] {
+            let line = data.next().ok_or_else(|| invalid("fewer entries than declared"))??;
+            let mut tokens = line.split_whitespace();
+            let r = parse_index(tokens.next(), n_rows)?;
+            let c = parse_index(tokens.next(), n_cols)?;
+            let v = if field == Field::Pattern { 1. } else { parse_value(tokens.next(), field)? };
+            triplets.push((r, c, v));
+        }
+    } else {
+        // column-major, only the stored triangle for the symmetric variants
+        for c in 0..n_cols {
+            let first = match symmetry {
+                Symmetry::General => 0,
+                Symmetry::Symmetric => c,
+                Symmetry::SkewSymmetric => c + 1,
+            };
+            for r in first..n_rows {
+                let line = data.next().ok_or_else(|| invalid("fewer entries than declared"))??;
+                triplets.push((r, c, parse_value(line.split_whitespace().next(), field)?));
+            }
+        }
+    }
+
+    if symmetry != Symmetry::General {
+        let sign = if symmetry == Symmetry::Symmetric { 1. } else { -1. };
+        let mirrored = triplets.iter()
+            .filter(|&&(r, c, _)| r != c)
+            .map(|&(r, c, v)| (c, r, sign * v))
+            .collect::<Vec<(usize, usize, f64)>>();
+        triplets.extend(mirrored);
+    }
+
+    Ok(Parsed { n_rows, n_cols, triplets })
+}
+
+// every entry must be matched by its mirror image; `entries` needs to cover
+// the non-zeros, `get` is the full matrix
+fn check_symmetry<I>(square: bool, entries: I, get: &dyn Fn(usize, usize) -> f64,
+                     symmetry: Symmetry) -> io::Result<()>
+    where I: Iterator<Item = (usize, usize, f64)>
+{
+    let sign = if symmetry == Symmetry::SkewSymmetric { -1. } else { 1. };
+    let mut entries = entries;
+    if symmetry != Symmetry::General && (!square || !entries.all(|(r, c, v)| get(c, r) == sign * v)) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  format!("matrix isn't {:?}", symmetry)));
+    }
+    Ok(())
+}
+
+fn banner(format: &str, field: Field, symmetry: Symmetry) -> String {
+    let field = match field {
+        Field::Real => "real",
+        Field::Integer => "integer",
+        Field::Pattern => "pattern",
+    };
+    let symmetry = match symmetry {
+        Symmetry::General => "general",
+        Symmetry::Symmetric => "symmetric",
+        Symmetry::SkewSymmetric => "skew-symmetric",
+    };
+    format!("%%MatrixMarket matrix {} {} {}", format, field, symmetry)
+}
+
+fn write_value<W: Write>(writer: &mut W, v: f64, field: Field) -> io::Result<()> {
+    match field {
+        Field::Real => write!(writer, "{}", v),
+        Field::Integer => write!(writer, "{}", v as i64),
+        Field::Pattern => Ok(()),
+    }
+}
+
+impl Matrix2d {
+    // Reads either variant, densifying coordinate files; duplicate
+    // coordinates are summed.
+    pub fn read_mtx<R: Read>(reader: R) -> io::Result<Matrix2d> {
+        let parsed = parse(reader)?;
+        if parsed.n_rows == 0 || parsed.n_cols == 0 {
+            return Err(invalid("empty matrix"));
+        }
+        let len = parsed.n_rows.checked_mul(parsed.n_cols)
+            .filter(|&len| len <= MAX_MTX_ELEMENTS)
+            .ok_or_else(|| invalid("matrix is too large to densify"))?;
+        let mut vec = vec![0.; len];
+        for (r, c, v) in parsed.triplets {
+            vec[r * parsed.n_cols + c] += v;
+        }
+        Ok(Matrix2d::from_vec_owned(vec, parsed.n_rows, parsed.n_cols))
+    }
+
+    // Writes the `array` variant. Asking for a symmetry the matrix doesn't
+    // have, or for the pattern field, is an InvalidInput error.
+    pub fn write_mtx<W: Write>(&self, writer: W, field: Field, symmetry: Symmetry) -> io::Result<()> {
+        if field == Field::Pattern {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "array files can't use the pattern field"));
+        }
+        let get = |r: usize, c: usize| self.matrix[r * self.rs + c * self.cs];
+        let entries = (0..self.n_rows).flat_map(|r| (0..self.n_cols).map(move |c| (r, c, get(r, c))));
+        check_symmetry(self.n_rows == self.n_cols, entries, &get, symmetry)?;
+
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "{}", banner("array", field, symmetry))?;
+        writeln!(writer, "{} {}", self.n_rows, self.n_cols)?;
+        for c in 0..self.n_cols {
+            let first = match symmetry {
+                Symmetry::General => 0,
+                Symmetry::Symmetric => c,
+                Symmetry::SkewSymmetric => c + 1,
+            };
+            for r in first..self.n_rows {
+                write_value(&mut writer, get(r, c), field)?;
+                writeln!(writer)?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+impl CsrMatrix {
+    // Reads either variant; entries of an `array` file are all stored,
+    // zeros included.
+    pub fn read_mtx<R: Read>(reader: R) -> io::Result<CsrMatrix> {
+        let parsed = parse(reader)?;
+        if parsed.n_rows > MAX_MTX_ELEMENTS {
+            return Err(invalid("too many rows"));
+        }
+        Ok(CsrMatrix::from_triplets(parsed.n_rows, parsed.n_cols, &parsed.triplets).unwrap())
+    }
+
+    // Writes the `coordinate` variant, keeping only the lower triangle for
+    // the symmetric variants.
+    pub fn write_mtx<W: Write>(&self, writer: W, field: Field, symmetry: Symmetry) -> io::Result<()> {
+        let get = |r: usize, c: usize| self.get(r, c).unwrap();
+        check_symmetry(self.get_rows() == self.get_cols(), self.triplets().into_iter(), &get, symmetry)?;
+
+        let entries = self.triplets().into_iter()
+            .filter(|&(r, c, _)| match symmetry {
+                Symmetry::General => true,
+                Symmetry::Symmetric => r >= c,
+                Symmetry::SkewSymmetric => r > c,
+            })
+            .collect::<Vec<(usize, usize, f64)>>();
+
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "{}", banner("coordinate", field, symmetry))?;
+        writeln!(writer, "{} {} {}", self.get_rows(), self.get_cols(), entries.len())?;
+        for (r, c, v) in entries {
+            write!(writer, "{} {}", r + 1, c + 1)?;
+            if field != Field::Pattern {
+                write!(writer, " ")?;
+                write_value(&mut writer, v, field)?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+}
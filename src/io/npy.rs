@@ -0,0 +1,375 @@
+use Matrix2d;
+use super::invalid;
+
+use flate2::Crc;
+use flate2::read::DeflateDecoder;
+
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dtype {
+    F32(Endian),
+    F64(Endian),
+    I64(Endian),
+}
+
+impl Dtype {
+    fn descr(&self) -> String {
+        let (kind, endian) = match *self {
+            Dtype::F32(e) => ("f4", e),
+            Dtype::F64(e) => ("f8", e),
+            Dtype::I64(e) => ("i8", e),
+        };
+        let order = if endian == Endian::Little { '<' } else { '>' };
+        format!("{}{}", order, kind)
+    }
+
+    fn from_descr(descr: &str) -> Option<Dtype> {
+        // `get` rather than `split_at`, so an empty or non-ascii descr is
+        // rejected instead of panicking
+        let (order, kind) = (descr.get(..1)?, descr.get(1..)?);
+        let endian = match order {
+            "<" => Endian::Little,
+            ">" => Endian::Big,
+            "=" | "|" if cfg!(target_endian = "little") => Endian::Little,
+            "=" | "|" => Endian::Big,
+            _ => return None,
+        };
+        match kind {
+            "f4" => Some(Dtype::F32(endian)),
+            "f8" => Some(Dtype::F64(endian)),
+            "i8" => Some(Dtype::I64(endian)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn item_size(&self) -> usize {
+        match *self {
+            Dtype::F32(_) => 4,
+            Dtype::F64(_) | Dtype::I64(_) => 8,
+        }
+    }
+
+    pub(crate) fn decode(&self, b: &[u8]) -> f64 {
+        macro_rules! read {
+            ($t:ty, $e:expr, $n:expr) => {{
+                let mut bytes = [0u8; $n];
+                bytes.copy_from_slice(b);
+                if $e == Endian::Little { <$t>::from_le_bytes(bytes) } else { <$t>::from_be_bytes(bytes) }
+            }}
+        }
+        match *self {
+            Dtype::F32(e) => read!(f32, e, 4) as f64,
+            Dtype::F64(e) => read!(f64, e, 8),
+            Dtype::I64(e) => read!(i64, e, 8) as f64,
+        }
+    }
+
+    fn encode(&self, v: f64, out: &mut Vec<u8>) {
+        match *self {
+            Dtype::F32(Endian::Little) => out.extend_from_slice(&(v as f32).to_le_bytes()),
+            Dtype::F32(Endian::Big) => out.extend_from_slice(&(v as f32).to_be_bytes()),
+            Dtype::F64(Endian::Little) => out.extend_from_slice(&v.to_le_bytes()),
+            Dtype::F64(Endian::Big) => out.extend_from_slice(&v.to_be_bytes()),
+            Dtype::I64(Endian::Little) => out.extend_from_slice(&(v as i64).to_le_bytes()),
+            Dtype::I64(Endian::Big) => out.extend_from_slice(&(v as i64).to_be_bytes()),
+        }
+    }
+}
+
+// Reads `len` bytes into `buf`, growing it as the data actually arrives
+// rather than allocating whatever size a header claims up front.
+fn read_exactly<R: Read>(reader: &mut R, len: u64, buf: &mut Vec<u8>) -> io::Result<()> {
+    if reader.take(len).read_to_end(buf)? as u64 != len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "data is truncated"));
+    }
+    Ok(())
+}
+
+// value of `key` in the header's python dict literal, up to the next ',' or
+// the closing ')' for tuples
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') { rest.find(')')? + 1 } else { rest.find([',', '}'])? };
+    Some(rest[..end].trim())
+}
+
+pub(crate) struct Header {
+    pub(crate) dtype: Dtype,
+    pub(crate) fortran_order: bool,
+    pub(crate) n_rows: usize,
+    pub(crate) n_cols: usize,
+}
+
+fn parse_header(header: &str) -> io::Result<Header> {
+    let descr = header_value(header, "descr")
+        .map(|d| d.trim_matches(|c| c == '\'' || c == '"'))
+        .ok_or_else(|| invalid("npy header has no descr"))?;
+    let dtype = Dtype::from_descr(descr)
+        .ok_or_else(|| invalid(&format!("unsupported npy dtype {}", descr)))?;
+
+    let fortran_order = match header_value(header, "fortran_order") {
+        Some("True") => true,
+        Some("False") => false,
+        _ => return Err(invalid("npy header has no fortran_order")),
+    };
+
+    let shape = header_value(header, "shape")
+        .ok_or_else(|| invalid("npy header has no shape"))?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim_end_matches('L').parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| invalid("npy shape isn't a tuple of integers"))?;
+
+    // scalars are 1 x 1 and vectors columns, like `ToMatrix2d` for Vec<f64>
+    let (n_rows, n_cols) = match shape.len() {
+        0 => (1, 1),
+        1 => (shape[0], 1),
+        2 => (shape[0], shape[1]),
+        _ => return Err(invalid("only arrays of up to 2 dimensions can be read")),
+    };
+    Ok(Header { dtype, fortran_order, n_rows, n_cols })
+}
+
+// reads the magic, version and header dict, returning the header and the
+// offset of the data from the start of the file
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> io::Result<(Header, usize)> {
+    let mut preamble = [0u8; 8];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..6] != MAGIC {
+        return Err(invalid("not an npy file"));
+    }
+
+    let (header_len, prefix_len) = match preamble[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            (u16::from_le_bytes(len) as usize, 10)
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            (u32::from_le_bytes(len) as usize, 12)
+        }
+        v => return Err(invalid(&format!("unsupported npy version {}", v))),
+    };
+    let mut header = Vec::new();
+    read_exactly(reader, header_len as u64, &mut header)?;
+    Ok((parse_header(&String::from_utf8_lossy(&header))?, prefix_len + header_len))
+}
+
+impl Matrix2d {
+    // Reads an .npy array of f4, f8 or i8 in either byte order. A Fortran
+    // ordered array keeps its column-major buffer, described by the strides.
+    pub fn read_npy<R: Read>(reader: R) -> io::Result<Matrix2d> {
+        let mut reader = BufReader::new(reader);
+        let (header, _) = read_header(&mut reader)?;
+
+        let item_size = header.dtype.item_size();
+        let len = header.n_rows.checked_mul(header.n_cols)
+            .and_then(|n| n.checked_mul(item_size))
+            .ok_or_else(|| invalid("npy shape is too large"))?;
+        let mut data = Vec::new();
+        read_exactly(&mut reader, len as u64, &mut data)?;
+        let matrix = data.chunks(item_size).map(|b| header.dtype.decode(b)).collect();
+
+        let (rs, cs) = if header.fortran_order { (1, header.n_rows) } else { (header.n_cols, 1) };
+        Ok(Matrix2d {
+            n_rows: header.n_rows,
+            n_cols: header.n_cols,
+            rs,
+            cs,
+            matrix: Arc::new(matrix),
+        })
+    }
+
+    // Writes a 2-d .npy array. Transposed matrices are written in Fortran
+    // order straight from their buffer; i8 values are truncated towards zero.
+    pub fn write_npy<W: Write>(&self, writer: W, dtype: Dtype) -> io::Result<()> {
+        let fortran_order = self.cs != 1 && self.rs == 1;
+        let mut header = format!("{{'descr': '{}', 'fortran_order': {}, 'shape': ({}, {}), }}",
+                                 dtype.descr(),
+                                 if fortran_order { "True" } else { "False" },
+                                 self.n_rows,
+                                 self.n_cols);
+        // the data starts on a 64 byte boundary
+        let pad = 63 - (MAGIC.len() + 4 + header.len()) % 64;
+        header.extend((0..pad).map(|_| ' '));
+        header.push('\n');
+
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[1, 0])?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+
+        let mut buf = Vec::with_capacity(self.n_cols * dtype.item_size());
+        let (outer, inner) = if fortran_order { (self.n_cols, self.n_rows) } else { (self.n_rows, self.n_cols) };
+        for i in 0..outer {
+            buf.clear();
+            for j in 0..inner {
+                let (row, col) = if fortran_order { (j, i) } else { (i, j) };
+                dtype.encode(self.matrix[row * self.rs + col * self.cs], &mut buf);
+            }
+            writer.write_all(&buf)?;
+        }
+        writer.flush()
+    }
+
+    // Reads the array saved as `name` (with or without the .npy suffix) from
+    // an .npz archive. Entries may be stored or deflated.
+    pub fn read_npz<R: Read + Seek>(reader: R, name: &str) -> io::Result<Matrix2d> {
+        let mut reader = reader;
+        let entries = zip_entries(&mut reader)?;
+        let entry = entries.iter()
+            .find(|e| e.name == name || e.name == format!("{}.npy", name))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no array {} in npz", name)))?;
+
+        // the local header repeats the name and may carry a different extra field
+        reader.seek(SeekFrom::Start(entry.offset))?;
+        let mut local = [0u8; 30];
+        reader.read_exact(&mut local)?;
+        if u32_at(&local, 0) != 0x0403_4b50 {
+            return Err(invalid("bad zip local header"));
+        }
+        let skip = u16_at(&local, 26) as i64 + u16_at(&local, 28) as i64;
+        reader.seek(SeekFrom::Current(skip))?;
+
+        let compressed = (&mut reader).take(entry.compressed_size);
+        let mut data = Vec::new();
+        match entry.method {
+            0 => { compressed.take(entry.size).read_to_end(&mut data)?; }
+            8 => { DeflateDecoder::new(compressed).take(entry.size).read_to_end(&mut data)?; }
+            m => return Err(invalid(&format!("unsupported zip compression method {}", m))),
+        }
+
+        let mut crc = Crc::new();
+        crc.update(&data);
+        if data.len() as u64 != entry.size || crc.sum() != entry.crc {
+            return Err(invalid("npz entry is corrupt"));
+        }
+        Matrix2d::read_npy(&data[..])
+    }
+}
+
+// names of the arrays in an .npz archive, without the .npy suffix
+pub fn npz_names<R: Read + Seek>(reader: R) -> io::Result<Vec<String>> {
+    let mut reader = reader;
+    Ok(zip_entries(&mut reader)?
+        .into_iter()
+        .map(|e| e.name.trim_end_matches(".npy").to_string())
+        .collect())
+}
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    offset: u64,
+}
+
+fn u16_at(b: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([b[at], b[at + 1]])
+}
+
+fn u32_at(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn u64_at(b: &[u8], at: usize) -> u64 {
+    u32_at(b, at) as u64 | (u32_at(b, at + 4) as u64) << 32
+}
+
+// reads the central directory, which numpy writes with the real sizes even
+// when the local headers carry zip64 placeholders
+fn zip_entries<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<ZipEntry>> {
+    // the end of central directory record sits within the last 64k + 22 bytes
+    let len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(65_557);
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0u8; tail_len as usize];
+    reader.read_exact(&mut tail)?;
+
+    let eocd = (0..tail.len().saturating_sub(21)).rev()
+        .find(|&i| u32_at(&tail, i) == 0x0605_4b50)
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let mut n_entries = u16_at(&tail, eocd + 10) as u64;
+    let mut dir_size = u32_at(&tail, eocd + 12) as u64;
+    let mut dir_offset = u32_at(&tail, eocd + 16) as u64;
+
+    // zip64 end of central directory, found through its locator
+    if eocd >= 20 && u32_at(&tail, eocd - 20) == 0x0706_4b50 {
+        reader.seek(SeekFrom::Start(u64_at(&tail, eocd - 12)))?;
+        let mut record = [0u8; 56];
+        reader.read_exact(&mut record)?;
+        n_entries = u64_at(&record, 32);
+        dir_size = u64_at(&record, 40);
+        dir_offset = u64_at(&record, 48);
+    }
+
+    if dir_offset.checked_add(dir_size).is_none_or(|end| end > len) {
+        return Err(invalid("zip central directory is out of bounds"));
+    }
+    reader.seek(SeekFrom::Start(dir_offset))?;
+    let mut dir = Vec::new();
+    read_exactly(reader, dir_size, &mut dir)?;
+
+    let mut entries = Vec::new();
+    let mut at = 0;
+    for _ in 0..n_entries {
+        if at + 46 > dir.len() || u32_at(&dir, at) != 0x0201_4b50 {
+            return Err(invalid("bad zip central directory"));
+        }
+        let name_len = u16_at(&dir, at + 28) as usize;
+        let extra_len = u16_at(&dir, at + 30) as usize;
+        let comment_len = u16_at(&dir, at + 32) as usize;
+        if at + 46 + name_len + extra_len + comment_len > dir.len() {
+            return Err(invalid("zip central directory is truncated"));
+        }
+        let name = String::from_utf8_lossy(&dir[at + 46..at + 46 + name_len]).into_owned();
+
+        let mut entry = ZipEntry {
+            name,
+            method: u16_at(&dir, at + 10),
+            crc: u32_at(&dir, at + 16),
+            compressed_size: u32_at(&dir, at + 20) as u64,
+            size: u32_at(&dir, at + 24) as u64,
+            offset: u32_at(&dir, at + 42) as u64,
+        };
+
+        // zip64 extra field, holding whichever of the values overflowed in order
+        let mut extra = &dir[at + 46 + name_len..at + 46 + name_len + extra_len];
+        while extra.len() >= 4 {
+            let (id, size) = (u16_at(extra, 0), u16_at(extra, 2) as usize);
+            if id == 0x0001 {
+                let mut field = extra.get(4..4 + size).ok_or_else(|| invalid("bad zip64 extra field"))?;
+                for value in [&mut entry.size, &mut entry.compressed_size, &mut entry.offset] {
+                    if *value == 0xFFFF_FFFF && field.len() >= 8 {
+                        *value = u64_at(field, 0);
+                        field = &field[8..];
+                    }
+                }
+            }
+            extra = &extra[(4 + size).min(extra.len())..];
+        }
+
+        entries.push(entry);
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
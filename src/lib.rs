@@ -2,23 +2,91 @@ extern crate rand;
 extern crate matrixmultiply;
 extern crate rayon;
 extern crate num_cpus;
+extern crate flate2;
+extern crate memmap2;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 use rand::distributions::{IndependentSample, Range};
 use rand::{random, SeedableRng, StdRng};
 
+use std::borrow::Cow;
+use std::sync::Arc;
+
 pub mod ext;
 pub mod utils;
-
-use utils::{vec_fn_op_threaded, get_chunk_size, vec_bin_op};
+pub mod loss;
+pub mod autograd;
+pub mod nn;
+pub mod optim;
+pub mod linear_model;
+pub mod cluster;
+pub mod pairwise;
+pub mod neighbors;
+pub mod tree;
+pub mod ensemble;
+pub mod metrics;
+pub mod io;
+pub mod sparse;
+pub mod datasets;
+pub mod expr;
+pub mod simd;
+
+use utils::{vec_bin_op_par, vec_fn_op_par, par_chunk_size};
+use utils::{Axis, Layout, Norm, par_dot, par_fill, par_fold_range, par_sum, reduce_chunk_size, par_gemm_threshold};
 use ext::traits::ToMatrix2d;
 
+// The buffer is shared copy-on-write: clones, transposes and reshapes point
+// at the same one, and it's only copied when one of them is mutated.
 #[derive(Clone)]
 pub struct Matrix2d {
     n_rows: usize,
     n_cols: usize,
     rs: usize,
     cs: usize,
-    matrix: Vec<f64>
+    matrix: Arc<Vec<f64>>
+}
+
+// output elements below which a `par_dot` block isn't split further
+const GEMM_MIN_BLOCK: usize = 64 * 64;
+
+// The output buffer of `par_dot`, shared by blocks that each write a
+// disjoint part of it.
+#[derive(Clone, Copy)]
+struct OutPtr(*mut f64);
+
+unsafe impl Send for OutPtr {}
+unsafe impl Sync for OutPtr {}
+
+// `a.dot(b)` restricted to output rows `rows.0..rows.1` and columns
+// `cols.0..cols.1`, halving the longer side until a block has at most
+// `min_block` elements; `c` has strides `c_strides`
+fn gemm_blocks(a: &Matrix2d, b: &Matrix2d, c: OutPtr, c_strides: (usize, usize),
+               rows: (usize, usize), cols: (usize, usize), min_block: usize) {
+    let (n_rows, n_cols) = (rows.1 - rows.0, cols.1 - cols.0);
+    if n_rows * n_cols <= min_block || (n_rows < 2 && n_cols < 2) {
+        unsafe {
+            matrixmultiply::dgemm(n_rows, a.n_cols, n_cols,
+                1., a.matrix.as_ptr().wrapping_add(rows.0 * a.rs), a.rs as isize, a.cs as isize,
+                b.matrix.as_ptr().wrapping_add(cols.0 * b.cs), b.rs as isize, b.cs as isize,
+                0., c.0.add(rows.0 * c_strides.0 + cols.0 * c_strides.1), c_strides.0 as isize, c_strides.1 as isize);
+        }
+        return;
+    }
+    // split on a multiple of 8 where possible, keeping the kernel's tiles whole
+    let split = |start: usize, len: usize| match len / 2 / 8 * 8 {
+        0 => start + len / 2,
+        half => start + half,
+    };
+    if n_rows >= n_cols {
+        let mid = split(rows.0, n_rows);
+        rayon::join(|| gemm_blocks(a, b, c, c_strides, (rows.0, mid), cols, min_block),
+                    || gemm_blocks(a, b, c, c_strides, (mid, rows.1), cols, min_block));
+    } else {
+        let mid = split(cols.0, n_cols);
+        rayon::join(|| gemm_blocks(a, b, c, c_strides, rows, (cols.0, mid), min_block),
+                    || gemm_blocks(a, b, c, c_strides, rows, (mid, cols.1), min_block));
+    }
 }
 
 impl Matrix2d {
@@ -28,7 +96,43 @@ impl Matrix2d {
             n_cols: n_cols,
             rs: n_cols,
             cs: 1,
-            matrix: (0..n_rows*n_cols).map(|_| 0.0).collect::<Vec<f64>>()
+            matrix: Arc::new((0..n_rows*n_cols).map(|_| 0.0).collect::<Vec<f64>>())
+        }
+    }
+
+    // zeros, stored in `layout`
+    pub fn new_with_layout(n_rows: usize, n_cols: usize, layout: Layout) -> Matrix2d {
+        Matrix2d::from_vec_with_layout(vec![0.; n_rows * n_cols], n_rows, n_cols, layout).unwrap()
+    }
+
+    // Takes `vec` as is, read in `layout` order, so e.g. Fortran-ordered
+    // data needs no copy. None if the length doesn't match the shape.
+    pub fn from_vec_with_layout(vec: Vec<f64>, n_rows: usize, n_cols: usize, layout: Layout) -> Option<Matrix2d> {
+        if vec.len() != n_rows * n_cols {
+            return None;
+        }
+        let (rs, cs) = match layout {
+            Layout::RowMajor => (n_cols, 1),
+            Layout::ColMajor => (1, n_rows),
+        };
+        Some(Matrix2d {
+            n_rows,
+            n_cols,
+            rs,
+            cs,
+            matrix: Arc::new(vec),
+        })
+    }
+
+    // a row-major matrix that takes ownership of `vec` instead of copying it
+    pub(crate) fn from_vec_owned(vec: Vec<f64>, n_rows: usize, n_cols: usize) -> Matrix2d {
+        debug_assert!(vec.len() == n_rows * n_cols);
+        Matrix2d {
+            n_rows,
+            n_cols,
+            rs: n_cols,
+            cs: 1,
+            matrix: Arc::new(vec),
         }
     }
 
@@ -38,7 +142,7 @@ impl Matrix2d {
             n_cols: vec[0].len(),
             rs: vec[0].len(),
             cs: 1,
-            matrix: vec.iter().flat_map(|el| el.iter().cloned() ).collect::<Vec<f64>>(),
+            matrix: Arc::new(vec.iter().flat_map(|el| el.iter().cloned() ).collect::<Vec<f64>>()),
         }
     }
 
@@ -48,8 +152,8 @@ impl Matrix2d {
             n_cols: n_cols,
             rs: n_cols,
             cs: 1,
-            matrix: (0..n_rows*n_cols)
-                .map(|_| random::<f64>()).collect::<Vec<f64>>()
+            matrix: Arc::new((0..n_rows*n_cols)
+                .map(|_| random::<f64>()).collect::<Vec<f64>>())
         }
     }
 
@@ -107,32 +211,122 @@ impl Matrix2d {
         &self.matrix
     }
 
+    // copies the buffer first if another matrix shares it
     pub fn get_matrix_mut(&mut self) -> &mut Vec<f64> {
-        &mut self.matrix
+        Arc::make_mut(&mut self.matrix)
+    }
+
+    // For callers about to overwrite every element: a shared buffer is
+    // swapped for a fresh one instead of being copied.
+    pub(crate) fn get_matrix_for_overwrite(&mut self) -> &mut Vec<f64> {
+        if Arc::get_mut(&mut self.matrix).is_none() {
+            self.matrix = Arc::new(vec![0.; self.matrix.len()]);
+        }
+        Arc::get_mut(&mut self.matrix).unwrap()
+    }
+
+    // The layout the buffer is densely packed in, or None for other strides.
+    // A single row or column is both; it's reported as RowMajor.
+    pub fn layout(&self) -> Option<Layout> {
+        if self.matrix.len() != self.n_rows * self.n_cols {
+            return None;
+        }
+        if (self.cs == 1 || self.n_cols <= 1) && (self.rs == self.n_cols || self.n_rows <= 1) {
+            Some(Layout::RowMajor)
+        } else if (self.rs == 1 || self.n_rows <= 1) && (self.cs == self.n_rows || self.n_cols <= 1) {
+            Some(Layout::ColMajor)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.layout().is_some()
+    }
+
+    fn is_layout(&self, layout: Layout) -> bool {
+        match layout {
+            Layout::RowMajor => self.layout() == Some(Layout::RowMajor),
+            // a single row or column is column-major too
+            Layout::ColMajor => self.transpose().layout() == Some(Layout::RowMajor),
+        }
+    }
+
+    // This matrix stored in `layout`; O(1), sharing the buffer, when it
+    // already is.
+    pub fn to_layout(&self, layout: Layout) -> Matrix2d {
+        if self.is_layout(layout) {
+            return self.clone();
+        }
+        let mut out = Matrix2d::new_with_layout(self.n_rows, self.n_cols, layout);
+        out.matrix = Arc::new(out.in_layout_of(self));
+        out
+    }
+
+    // `to_layout(Layout::RowMajor)`, the order `reshape` and `ravel` read in
+    pub fn as_standard_layout(&self) -> Matrix2d {
+        self.to_layout(Layout::RowMajor)
+    }
+
+    pub fn shares_buffer(&self, m: &Matrix2d) -> bool {
+        Arc::ptr_eq(&self.matrix, &m.matrix)
     }
 
+    // The product is column-major when the operands are, so Fortran-ordered
+    // data stays that way, and row-major otherwise. Either way `dgemm` reads
+    // both operands through their strides, without copying them.
+    fn product_layout(&self, m: &Matrix2d) -> Layout {
+        let (a, b) = (self.layout(), m.layout());
+        if a != Some(Layout::RowMajor) && b != Some(Layout::RowMajor)
+            && (a == Some(Layout::ColMajor) || b == Some(Layout::ColMajor)) {
+            Layout::ColMajor
+        } else {
+            Layout::RowMajor
+        }
+    }
+
+    // Goes through `par_dot` once the product takes `par_gemm_threshold`
+    // multiply-adds, `dot_serial` below that.
     pub fn dot(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        if self.n_cols == m.get_rows() && self.n_rows * self.n_cols * m.get_cols() >= par_gemm_threshold() {
+            return self.par_dot(m);
+        }
+        self.dot_serial(m)
+    }
+
+    // a single `dgemm` call on the current thread, whatever the size
+    pub fn dot_serial(&self, m: &Matrix2d) -> Option<Matrix2d> {
         if self.n_cols == m.get_rows() {
-            let mut c = vec![0.; self.n_rows * m.get_cols()];
+            let mut c = Matrix2d::new_with_layout(self.n_rows, m.get_cols(), self.product_layout(m));
             // amazing magic happens here
             unsafe {
                 matrixmultiply::dgemm(self.n_rows, self.n_cols, m.get_cols(),
                     1., self.get_matrix().as_ptr(), self.rs as isize, self.cs as isize,
                     m.get_matrix().as_ptr(), m.get_row_stride() as isize, m.get_col_stride() as isize,
-                    0., c.as_mut_ptr(), m.get_cols() as isize, 1);
+                    0., c.get_matrix_mut().as_mut_ptr(), c.rs as isize, c.cs as isize);
             }
-
-            return Some(Matrix2d {
-                n_rows: self.n_rows,
-                n_cols: m.get_cols(),
-                rs: m.get_cols(),
-                cs: 1,
-                matrix: c,
-            });
+            return Some(c);
         }
         None
     }
 
+    // Splits the output into blocks of rows and columns and runs `dgemm` for
+    // each on the rayon pool. Every element is still accumulated over the
+    // full inner dimension in the same order, so the result is bit-identical
+    // to the serial path.
+    pub fn par_dot(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        if self.n_cols != m.get_rows() {
+            return None;
+        }
+        let (n_rows, n_cols) = (self.n_rows, m.get_cols());
+        let mut c = Matrix2d::new_with_layout(n_rows, n_cols, self.product_layout(m));
+        let c_strides = (c.rs, c.cs);
+        // a few blocks per thread to even out the load, none too small to pack well
+        let min_block = (n_rows * n_cols / (4 * num_cpus::get())).max(GEMM_MIN_BLOCK);
+        gemm_blocks(self, m, OutPtr(c.get_matrix_mut().as_mut_ptr()), c_strides, (0, n_rows), (0, n_cols), min_block);
+        Some(c)
+    }
+
     pub fn apply_fn<F>(&self, f: F) -> Matrix2d
         where F: Fn(f64) -> f64
     {
@@ -157,99 +351,247 @@ impl Matrix2d {
             n_cols: self.n_cols,
             rs: self.rs,
             cs: self.cs,
-            matrix: out_vec
+            matrix: Arc::new(out_vec)
         }
     }
 
     pub fn par_apply_fn<F>(&self, f: &F) -> Matrix2d
-        where F: Sync + Send + 'static + Fn(f64) -> f64
+        where F: Sync + Fn(f64) -> f64
     {
+        let len = self.matrix.len();
+        let mut out = vec![0.; len];
+        vec_fn_op_par(&self.matrix, &mut out, par_chunk_size(len), f);
         Matrix2d {
             n_rows: self.n_rows,
             n_cols: self.n_cols,
             rs: self.rs,
             cs: self.cs,
-            matrix: vec_fn_op_threaded(self.get_matrix(), &1024, f)
+            matrix: Arc::new(out)
         }
     }
 
-    pub fn scale(&self, scalar: f64) -> Matrix2d {
+    pub fn par_scale(&self, scalar: f64) -> Matrix2d {
+        self.par_apply_fn(&|x| x * scalar)
+    }
+
+    // `m`'s elements in this matrix's buffer order, so the two buffers can be
+    // zipped even when one of them is transposed
+    fn in_layout_of(&self, m: &Matrix2d) -> Vec<f64> {
+        let mut vec = vec![0.; self.matrix.len()];
+        for row in 0..self.n_rows {
+            for col in 0..self.n_cols {
+                vec[row * self.rs + col * self.cs] = m.matrix[row * m.rs + col * m.cs];
+            }
+        }
+        vec
+    }
+
+    // `m`'s buffer when it's laid out like this matrix, else `in_layout_of`
+    fn matched<'a>(&self, m: &'a Matrix2d) -> Cow<'a, [f64]> {
+        if self.rs == m.rs && self.cs == m.cs {
+            Cow::Borrowed(&m.matrix[..])
+        } else {
+            Cow::Owned(self.in_layout_of(m))
+        }
+    }
+
+    // applies `f` to matching elements on the rayon pool, keeping this
+    // matrix's layout; the chunk size comes from `utils::par_chunk_size`
+    pub fn par_zip_fn<F>(&self, m: &Matrix2d, f: &F) -> Option<Matrix2d>
+        where F: Sync + Fn(f64, f64) -> f64
+    {
+        if self.n_rows != m.n_rows || self.n_cols != m.n_cols {
+            return None;
+        }
+        let relaid;
+        let other = if self.rs == m.rs && self.cs == m.cs {
+            &m.matrix[..]
+        } else {
+            relaid = self.in_layout_of(m);
+            &relaid[..]
+        };
+
         let len = self.matrix.len();
-        let xs = &self.get_matrix()[..len];
+        let mut out = vec![0.; len];
+        vec_bin_op_par(&self.matrix, other, &mut out, par_chunk_size(len), f);
+        Some(Matrix2d {
+            n_rows: self.n_rows,
+            n_cols: self.n_cols,
+            rs: self.rs,
+            cs: self.cs,
+            matrix: Arc::new(out)
+        })
+    }
 
-        let mut out_vec = Vec::with_capacity(len);
-        unsafe {
-            out_vec.set_len(len);
+    pub fn par_addition(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        self.par_zip_fn(m, &|x, y| x + y)
+    }
+
+    pub fn par_subtract(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        self.par_zip_fn(m, &|x, y| x - y)
+    }
+
+    pub fn par_mult(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        self.par_zip_fn(m, &|x, y| x * y)
+    }
+
+    pub fn par_sum(&self) -> f64 {
+        par_sum(&self.matrix, reduce_chunk_size())
+    }
+
+    pub fn par_norm(&self, norm: Norm) -> f64 {
+        let xs = &self.matrix;
+        let chunk_size = reduce_chunk_size();
+        match norm {
+            Norm::L1 => par_fold_range(0, xs.len(), chunk_size,
+                                       &|a, b| xs[a..b].iter().map(|x| x.abs()).sum(),
+                                       &|x, y| x + y),
+            Norm::L2 => par_dot(xs, xs, chunk_size).sqrt(),
+            Norm::Max => par_fold_range(0, xs.len(), chunk_size,
+                                        &|a, b| xs[a..b].iter().fold(0., |m, x| x.abs().max(m)),
+                                        &f64::max),
         }
+    }
 
-        {
-            let out_slice = &mut out_vec[..len];
+    // sum of the elementwise products, the dot product for vectors
+    pub fn par_inner(&self, m: &Matrix2d) -> Option<f64> {
+        if self.n_rows != m.n_rows || self.n_cols != m.n_cols {
+            return None;
+        }
+        if self.rs == m.rs && self.cs == m.cs {
+            return Some(par_dot(&self.matrix, &m.matrix, reduce_chunk_size()));
+        }
+        // walk the logical row-major order of both
+        let n_cols = self.n_cols;
+        Some(par_fold_range(0, self.matrix.len(), reduce_chunk_size(),
+                            &|a, b| (a..b).map(|i| {
+                                let (row, col) = (i / n_cols, i % n_cols);
+                                self.matrix[row * self.rs + col * self.cs] * m.matrix[row * m.rs + col * m.cs]
+                            }).sum(),
+                            &|x, y| x + y))
+    }
 
-            for i in 0..len {
-                out_slice[i] = xs[i] * scalar;
-            }
+    // Reduces every line along `axis` with `f(line_index, element_stride,
+    // line_start)`, lines going to the rayon pool in groups.
+    fn par_reduce_axis<F>(&self, axis: Axis, f: &F) -> Matrix2d
+        where F: Fn(usize, usize, usize) -> f64 + Sync
+    {
+        // (number of lines, their length, stride between lines, stride along a line)
+        let (n_lines, line_len, line_stride, step) = match axis {
+            Axis::Rows => (self.n_rows, self.n_cols, self.rs, self.cs),
+            Axis::Cols => (self.n_cols, self.n_rows, self.cs, self.rs),
+        };
+        let mut out = vec![0.; n_lines];
+        par_fill(&mut out, 0, (reduce_chunk_size() / line_len.max(1)).max(1),
+                 &|line| f(line_len, step, line * line_stride));
+
+        let (n_rows, n_cols) = if axis == Axis::Rows { (n_lines, 1) } else { (1, n_lines) };
+        Matrix2d {
+            n_rows,
+            n_cols,
+            rs: n_cols,
+            cs: 1,
+            matrix: Arc::new(out)
         }
+    }
+
+    pub fn par_sum_axis(&self, axis: Axis) -> Matrix2d {
+        self.par_reduce_axis(axis, &|len, step, start| {
+            (0..len).map(|i| self.matrix[start + i * step]).sum()
+        })
+    }
+
+    pub fn par_mean_axis(&self, axis: Axis) -> Matrix2d {
+        self.par_reduce_axis(axis, &|len, step, start| {
+            (0..len).map(|i| self.matrix[start + i * step]).sum::<f64>() / len as f64
+        })
+    }
+
+    pub fn par_max_axis(&self, axis: Axis) -> Matrix2d {
+        self.par_reduce_axis(axis, &|len, step, start| {
+            (0..len).map(|i| self.matrix[start + i * step]).fold(f64::NEG_INFINITY, f64::max)
+        })
+    }
+
+    pub fn par_min_axis(&self, axis: Axis) -> Matrix2d {
+        self.par_reduce_axis(axis, &|len, step, start| {
+            (0..len).map(|i| self.matrix[start + i * step]).fold(f64::INFINITY, f64::min)
+        })
+    }
+
+    pub fn scale(&self, scalar: f64) -> Matrix2d {
+        let len = self.matrix.len();
+        let mut out_vec = Vec::with_capacity(len);
+        unsafe {
+            out_vec.set_len(len);
+        }
+        simd::scale(&self.matrix, scalar, &mut out_vec);
 
         Matrix2d {
             n_rows: self.n_rows,
             n_cols: self.n_cols,
             rs: self.rs,
             cs: self.cs,
-            matrix: out_vec
+            matrix: Arc::new(out_vec)
 
         }
     }
 
-    pub fn mult(&self, m: &Matrix2d) -> Option<Matrix2d> {
+    // `op` over the two buffers, `m` in this matrix's layout
+    fn zip_simd(&self, m: &Matrix2d, op: fn(&[f64], &[f64], &mut [f64])) -> Option<Matrix2d> {
         if  self.get_cols() == m.get_cols() &&
             self.get_rows() == m.get_rows() {
+            let len = self.matrix.len();
+            let mut out_vec = Vec::with_capacity(len);
+            unsafe {
+                out_vec.set_len(len);
+            }
+            op(&self.matrix, &self.matched(m), &mut out_vec);
             return Some(
                 Matrix2d {
                     n_rows: self.n_rows,
                     n_cols: self.n_cols,
                     rs: self.rs,
                     cs: self.cs,
-                    matrix: vec_bin_op(self.get_matrix(), m.get_matrix(), |x, y| x * y)
-            });
+                    matrix: Arc::new(out_vec)
+                });
         }
         None
     }
 
+    pub fn mult(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        self.zip_simd(m, simd::mul)
+    }
+
     pub fn subtract(&self, m: &Matrix2d) -> Option<Matrix2d> {
-        if  self.get_cols() == m.get_cols() &&
-            self.get_rows() == m.get_rows() {
-            return Some(
-                Matrix2d {
-                    n_rows: self.n_rows,
-                    n_cols: self.n_cols,
-                    rs: self.rs,
-                    cs: self.cs,
-                    matrix: vec_bin_op(self.get_matrix(), m.get_matrix(), |x, y| x - y)
-                });
-        }
-        None
+        self.zip_simd(m, simd::sub)
     }
 
     pub fn addition(&self, m: &Matrix2d) -> Option<Matrix2d> {
-        if  self.get_cols() == m.get_cols() &&
-            self.get_rows() == m.get_rows() {
-            return Some(
-                Matrix2d {
-                    n_rows: self.n_rows,
-                    n_cols: self.n_cols,
-                    rs: self.rs,
-                    cs: self.cs,
-                    matrix: vec_bin_op(self.get_matrix(), m.get_matrix(), |x, y| x + y)
-                });
-        }
-        None
+        self.zip_simd(m, simd::add)
     }
 
+    // the elements in row-major order, whatever the storage layout
     pub fn ravel(&self) -> Vec<f64> {
-        self.matrix.clone()
+        if self.layout() != Some(Layout::RowMajor) {
+            return self.as_standard_layout().into_ravel();
+        }
+        self.matrix.to_vec()
     }
 
+    // `ravel` without the copy when nothing else shares a row-major buffer
+    pub fn into_ravel(self) -> Vec<f64> {
+        if self.layout() != Some(Layout::RowMajor) {
+            return self.as_standard_layout().into_ravel();
+        }
+        Arc::try_unwrap(self.matrix).unwrap_or_else(|shared| shared.to_vec())
+    }
+
+    // reads the elements row by row; O(1) when already row-major
     pub fn reshape(&self, n_rows: usize, n_cols: usize) -> Option<Matrix2d> {
+        if self.layout() != Some(Layout::RowMajor) {
+            return self.as_standard_layout().reshape(n_rows, n_cols);
+        }
         if self.matrix.len() / n_cols == n_rows {
             return Some(
                 Matrix2d {
@@ -272,7 +614,7 @@ impl Matrix2d {
                     n_cols: n_cols,
                     rs: n_cols,
                     cs: 1,
-                    matrix: vec.clone()
+                    matrix: Arc::new(vec.clone())
                 }
             );
         }
@@ -303,7 +645,7 @@ impl Matrix2d {
             n_cols: self.n_cols,
             rs: self.rs,
             cs: self.cs,
-            matrix: matrix_clone.clone()
+            matrix: Arc::new(matrix_clone.clone())
         }
     }
 
@@ -317,7 +659,7 @@ impl Matrix2d {
             n_cols: self.n_cols,
             rs: self.rs,
             cs: self.cs,
-            matrix: vec![0.; self.n_rows * self.n_cols]
+            matrix: Arc::new(vec![0.; self.n_rows * self.n_cols])
         };
 
         for row in 0..self.n_rows {
@@ -0,0 +1,369 @@
+use Matrix2d;
+use loss::{binary_cross_entropy_with_logits, categorical_cross_entropy_with_logits};
+use metrics::accuracy_score;
+use utils::{sigmoid, soft_threshold};
+use super::broadcast_row;
+
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Penalty {
+    None,
+    L1(f64),
+    L2(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solver {
+    GradientDescent { learning_rate: f64 },
+    // `memory` is the number of correction pairs kept; with an L1 penalty
+    // this runs as OWL-QN
+    Lbfgs { memory: usize },
+}
+
+// Binary targets get a single logit column (sigmoid), more than two classes
+// a softmax over one column per class. Labels are read from the single
+// column of `y` and may be any distinct f64 values.
+pub struct LogisticRegression {
+    penalty: Penalty,
+    solver: Solver,
+    max_iter: usize,
+    tolerance: f64,
+    n_iter: usize,
+    classes: Vec<f64>,
+    coefficients: Option<Matrix2d>,
+    intercept: Option<Matrix2d>,
+}
+
+struct Problem<'a> {
+    x: &'a Matrix2d,
+    targets: Matrix2d,
+    n_features: usize,
+    n_outputs: usize,
+    l2: f64,
+}
+
+impl<'a> Problem<'a> {
+    fn unpack(&self, w: &[f64]) -> (Matrix2d, Matrix2d) {
+        let split = self.n_features * self.n_outputs;
+        (Matrix2d::from_vec_owned(w[..split].to_vec(), self.n_features, self.n_outputs),
+         Matrix2d::from_vec_owned(w[split..].to_vec(), 1, self.n_outputs))
+    }
+
+    fn is_penalized(&self, i: usize) -> bool {
+        i < self.n_features * self.n_outputs
+    }
+
+    // smooth part of the objective (mean log loss + L2) and its gradient
+    fn evaluate(&self, w: &[f64]) -> (f64, Vec<f64>) {
+        let (coef, intercept) = self.unpack(w);
+        let logits = broadcast_row(&self.x.dot(&coef).unwrap(), &intercept, |a, b| a + b);
+
+        let (loss, grad) = if self.n_outputs == 1 {
+            binary_cross_entropy_with_logits(&logits, &self.targets).unwrap()
+        } else {
+            categorical_cross_entropy_with_logits(&logits, &self.targets).unwrap()
+        };
+        // the binary loss averages over elements, which is the same as rows here
+        let grad_coef = self.x.transpose().dot(&grad).unwrap();
+        let grad_intercept = (0..self.n_outputs).map(|c| grad.get_col(c).unwrap().iter().sum::<f64>());
+
+        let mut g = (0..grad_coef.get_rows())
+            .flat_map(|r| grad_coef.get_row(r).unwrap())
+            .chain(grad_intercept)
+            .collect::<Vec<f64>>();
+
+        let mut value = loss;
+        for i in 0..w.len() {
+            if self.is_penalized(i) {
+                value += 0.5 * self.l2 * w[i] * w[i];
+                g[i] += self.l2 * w[i];
+            }
+        }
+        (value, g)
+    }
+
+    fn l1_norm(&self, w: &[f64]) -> f64 {
+        (0..w.len()).filter(|&i| self.is_penalized(i)).map(|i| w[i].abs()).sum()
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn gradient_descent(problem: &Problem, w: &mut [f64], l1: f64, learning_rate: f64,
+                    max_iter: usize, tolerance: f64) -> usize {
+    for iter in 0..max_iter {
+        let (_, g) = problem.evaluate(w);
+        let mut max_delta = 0f64;
+        for i in 0..w.len() {
+            let mut next = w[i] - learning_rate * g[i];
+            if problem.is_penalized(i) {
+                next = soft_threshold(next, learning_rate * l1);
+            }
+            max_delta = max_delta.max((next - w[i]).abs());
+            w[i] = next;
+        }
+        if max_delta < tolerance {
+            return iter + 1;
+        }
+    }
+    max_iter
+}
+
+// L1 pseudo-gradient: the minimum-norm subgradient of the full objective
+fn pseudo_gradient(problem: &Problem, w: &[f64], g: &[f64], l1: f64) -> Vec<f64> {
+    (0..w.len())
+        .map(|i| {
+            if !problem.is_penalized(i) || l1 == 0. {
+                g[i]
+            } else if w[i] > 0. {
+                g[i] + l1
+            } else if w[i] < 0. {
+                g[i] - l1
+            } else if g[i] + l1 < 0. {
+                g[i] + l1
+            } else if g[i] - l1 > 0. {
+                g[i] - l1
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
+
+fn lbfgs(problem: &Problem, w: &mut Vec<f64>, l1: f64, memory: usize,
+         max_iter: usize, tolerance: f64) -> usize {
+    let mut history: VecDeque<(Vec<f64>, Vec<f64>, f64)> = VecDeque::with_capacity(memory);
+    let (mut f, mut g) = problem.evaluate(w);
+    f += l1 * problem.l1_norm(w);
+
+    for iter in 0..max_iter {
+        let pg = pseudo_gradient(problem, w, &g, l1);
+        if pg.iter().fold(0f64, |m, x| m.max(x.abs())) < tolerance {
+            return iter;
+        }
+
+        // two-loop recursion for d = -H pg
+        let mut q = pg.clone();
+        let mut alphas = Vec::with_capacity(history.len());
+        for (s, y, rho) in history.iter().rev() {
+            let a = rho * dot(s, &q);
+            for (qi, yi) in q.iter_mut().zip(y.iter()) {
+                *qi -= a * yi;
+            }
+            alphas.push(a);
+        }
+        if let Some((s, y, _)) = history.back() {
+            let gamma = dot(s, y) / dot(y, y);
+            for qi in q.iter_mut() {
+                *qi *= gamma;
+            }
+        }
+        for ((s, y, rho), a) in history.iter().zip(alphas.iter().rev()) {
+            let b = rho * dot(y, &q);
+            for (qi, si) in q.iter_mut().zip(s.iter()) {
+                *qi += (a - b) * si;
+            }
+        }
+        let mut d = q.iter().map(|x| -x).collect::<Vec<f64>>();
+
+        // keep the direction inside the orthant the pseudo-gradient points to
+        let orthant = (0..w.len())
+            .map(|i| if w[i] != 0. { w[i].signum() } else { -pg[i].signum() })
+            .collect::<Vec<f64>>();
+        if l1 > 0. {
+            for i in 0..d.len() {
+                if problem.is_penalized(i) && d[i] * -pg[i] <= 0. {
+                    d[i] = 0.;
+                }
+            }
+        }
+        if dot(&d, &pg) >= 0. {
+            d = pg.iter().map(|x| -x).collect();
+            history.clear();
+        }
+
+        // backtracking line search on the full objective
+        let mut step = if history.is_empty() { 1. / dot(&pg, &pg).sqrt().max(1.) } else { 1. };
+        let mut accepted = None;
+        for _ in 0..50 {
+            let mut next = (0..w.len()).map(|i| w[i] + step * d[i]).collect::<Vec<f64>>();
+            if l1 > 0. {
+                for i in 0..next.len() {
+                    if problem.is_penalized(i) && next[i] * orthant[i] <= 0. {
+                        next[i] = 0.;
+                    }
+                }
+            }
+            let (f_next, g_next) = problem.evaluate(&next);
+            let f_next = f_next + l1 * problem.l1_norm(&next);
+            let decrease = (0..w.len()).map(|i| pg[i] * (next[i] - w[i])).sum::<f64>();
+            if f_next <= f + 1e-4 * decrease {
+                accepted = Some((next, f_next, g_next));
+                break;
+            }
+            step *= 0.5;
+        }
+
+        let (next, f_next, g_next) = match accepted {
+            Some(a) => a,
+            None => return iter + 1,
+        };
+
+        let s = (0..w.len()).map(|i| next[i] - w[i]).collect::<Vec<f64>>();
+        let y = (0..w.len()).map(|i| g_next[i] - g[i]).collect::<Vec<f64>>();
+        let sy = dot(&s, &y);
+        if sy > 1e-12 {
+            if history.len() == memory {
+                history.pop_front();
+            }
+            history.push_back((s, y, 1. / sy));
+        }
+
+        let converged = (f - f_next).abs() <= tolerance * f.abs().max(1.);
+        *w = next;
+        f = f_next;
+        g = g_next;
+        if converged {
+            return iter + 1;
+        }
+    }
+    max_iter
+}
+
+impl LogisticRegression {
+    pub fn new(penalty: Penalty, solver: Solver) -> LogisticRegression {
+        LogisticRegression::with_params(penalty, solver, 1000, 1e-8)
+    }
+
+    pub fn with_params(penalty: Penalty, solver: Solver, max_iter: usize, tolerance: f64) -> LogisticRegression {
+        LogisticRegression {
+            penalty,
+            solver,
+            max_iter,
+            tolerance,
+            n_iter: 0,
+            classes: Vec::new(),
+            coefficients: None,
+            intercept: None,
+        }
+    }
+
+    // `y` is a single column of class labels; None if the shapes disagree or
+    // there are fewer than two classes
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() == 0 {
+            return None;
+        }
+
+        let labels = y.get_col(0).unwrap();
+        let mut classes = labels.clone();
+        classes.sort_by(f64::total_cmp);
+        classes.dedup();
+        if classes.len() < 2 {
+            return None;
+        }
+
+        let n_outputs = if classes.len() == 2 { 1 } else { classes.len() };
+        let targets = if n_outputs == 1 {
+            let t = labels.iter().map(|&l| if l == classes[1] { 1. } else { 0. }).collect();
+            Matrix2d::from_vec_owned(t, labels.len(), 1)
+        } else {
+            let t = labels.iter()
+                .flat_map(|&l| classes.iter().map(move |&c| if l == c { 1. } else { 0. }))
+                .collect();
+            Matrix2d::from_vec_owned(t, labels.len(), n_outputs)
+        };
+
+        let (l1, l2) = match self.penalty {
+            Penalty::None => (0., 0.),
+            Penalty::L1(alpha) => (alpha, 0.),
+            Penalty::L2(alpha) => (0., alpha),
+        };
+
+        let problem = Problem {
+            x,
+            targets,
+            n_features: x.get_cols(),
+            n_outputs,
+            l2,
+        };
+
+        let mut w = vec![0.; (x.get_cols() + 1) * n_outputs];
+        self.n_iter = match self.solver {
+            Solver::GradientDescent { learning_rate } =>
+                gradient_descent(&problem, &mut w, l1, learning_rate, self.max_iter, self.tolerance),
+            Solver::Lbfgs { memory } =>
+                lbfgs(&problem, &mut w, l1, memory.max(1), self.max_iter, self.tolerance),
+        };
+
+        let (coef, intercept) = problem.unpack(&w);
+        self.classes = classes;
+        self.coefficients = Some(coef);
+        self.intercept = Some(intercept);
+        Some(())
+    }
+
+    // raw logits: n x 1 for binary problems, n x n_classes otherwise
+    pub fn decision_function(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let coef = self.coefficients.as_ref()?;
+        let out = x.dot(coef)?;
+        Some(broadcast_row(&out, self.intercept.as_ref().unwrap(), |a, b| a + b))
+    }
+
+    // n x n_classes, columns ordered like `get_classes`
+    pub fn predict_proba(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let logits = self.decision_function(x)?;
+        let mut out = Vec::with_capacity(x.get_rows() * self.classes.len());
+
+        for r in 0..logits.get_rows() {
+            let z = logits.get_row(r).unwrap();
+            if z.len() == 1 {
+                let p = sigmoid(z[0]);
+                out.push(1. - p);
+                out.push(p);
+            } else {
+                let max = z.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let exps = z.iter().map(|v| (v - max).exp()).collect::<Vec<f64>>();
+                let sum = exps.iter().sum::<f64>();
+                out.extend(exps.iter().map(|e| e / sum));
+            }
+        }
+        Some(Matrix2d::from_vec_owned(out, x.get_rows(), self.classes.len()))
+    }
+
+    // n x 1 of predicted class labels
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let proba = self.predict_proba(x)?;
+        let labels = (0..proba.get_rows())
+            .map(|r| {
+                let row = proba.get_row(r).unwrap();
+                let best = (0..row.len()).fold(0, |b, i| if row[i] > row[b] { i } else { b });
+                self.classes[best]
+            })
+            .collect::<Vec<f64>>();
+        Some(Matrix2d::from_vec_owned(labels, x.get_rows(), 1))
+    }
+
+    pub fn score(&self, x: &Matrix2d, y: &Matrix2d) -> Option<f64> {
+        accuracy_score(y, &self.predict(x)?)
+    }
+
+    pub fn get_classes(&self) -> &[f64] {
+        &self.classes
+    }
+
+    pub fn get_coefficients(&self) -> Option<&Matrix2d> {
+        self.coefficients.as_ref()
+    }
+
+    pub fn get_intercept(&self) -> Option<&Matrix2d> {
+        self.intercept.as_ref()
+    }
+
+    pub fn get_n_iter(&self) -> usize {
+        self.n_iter
+    }
+}
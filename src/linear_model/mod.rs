@@ -0,0 +1,81 @@
+use Matrix2d;
+use utils::flatten;
+
+pub mod regression;
+pub mod logistic;
+
+fn col_means(m: &Matrix2d) -> Matrix2d {
+    let n = m.get_rows() as f64;
+    let vec = (0..m.get_cols())
+        .map(|col| m.get_col(col).unwrap().iter().sum::<f64>() / n)
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, 1, m.get_cols())
+}
+
+// applies `f(x, row[col])` to every element, `row` being a 1 x n_cols matrix
+fn broadcast_row<F>(m: &Matrix2d, row: &Matrix2d, f: F) -> Matrix2d
+    where F: Fn(f64, f64) -> f64
+{
+    let r = row.get_row(0).unwrap();
+    let vec = (0..m.get_rows())
+        .flat_map(|idx| {
+            m.get_row(idx).unwrap().iter()
+                .zip(r.iter())
+                .map(|(&x, &y)| f(x, y))
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, m.get_rows(), m.get_cols())
+}
+
+// Least squares solution of `a x = b` through Householder QR, so the
+// condition number isn't squared the way the normal equations would.
+// Returns None when `a` has fewer rows than columns or is rank deficient.
+fn lstsq(a: &Matrix2d, b: &Matrix2d) -> Option<Matrix2d> {
+    let (m, n, k) = (a.get_rows(), a.get_cols(), b.get_cols());
+    if m < n || b.get_rows() != m {
+        return None;
+    }
+
+    let mut r = flatten(a);
+    let mut qtb = flatten(b);
+    let scale = (0..n)
+        .map(|j| (0..m).map(|i| r[i * n + j] * r[i * n + j]).sum::<f64>().sqrt())
+        .fold(0., f64::max);
+
+    for j in 0..n {
+        let norm = (j..m).map(|i| r[i * n + j] * r[i * n + j]).sum::<f64>().sqrt();
+        if norm <= 1e-12 * scale || norm == 0. {
+            return None;
+        }
+
+        let alpha = if r[j * n + j] > 0. { -norm } else { norm };
+        let mut v = (j..m).map(|i| r[i * n + j]).collect::<Vec<f64>>();
+        v[0] -= alpha;
+        let v_norm2 = v.iter().map(|x| x * x).sum::<f64>();
+
+        if v_norm2 > 0. {
+            for col in j..n {
+                let s = 2. * (j..m).map(|i| v[i - j] * r[i * n + col]).sum::<f64>() / v_norm2;
+                for i in j..m {
+                    r[i * n + col] -= s * v[i - j];
+                }
+            }
+            for col in 0..k {
+                let s = 2. * (j..m).map(|i| v[i - j] * qtb[i * k + col]).sum::<f64>() / v_norm2;
+                for i in j..m {
+                    qtb[i * k + col] -= s * v[i - j];
+                }
+            }
+        }
+    }
+
+    let mut x = vec![0.; n * k];
+    for col in 0..k {
+        for i in (0..n).rev() {
+            let s = (i + 1..n).map(|j| r[i * n + j] * x[j * k + col]).sum::<f64>();
+            x[i * k + col] = (qtb[i * k + col] - s) / r[i * n + i];
+        }
+    }
+    Some(Matrix2d::from_vec_owned(x, n, k))
+}
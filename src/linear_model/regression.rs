@@ -0,0 +1,205 @@
+use Matrix2d;
+use metrics::r2_score;
+use utils::{flatten, soft_threshold};
+use super::{col_means, broadcast_row, lstsq};
+
+struct Fitted {
+    coefficients: Matrix2d,
+    intercept: Matrix2d,
+}
+
+impl Fitted {
+    fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let out = x.dot(&self.coefficients)?;
+        Some(broadcast_row(&out, &self.intercept, |a, b| a + b))
+    }
+}
+
+struct Centered {
+    x: Matrix2d,
+    y: Matrix2d,
+    x_mean: Matrix2d,
+    y_mean: Matrix2d,
+}
+
+fn center(x: &Matrix2d, y: &Matrix2d, fit_intercept: bool) -> Option<Centered> {
+    if x.get_rows() != y.get_rows() || x.get_rows() == 0 {
+        return None;
+    }
+
+    let (x_mean, y_mean) = if fit_intercept {
+        (col_means(x), col_means(y))
+    } else {
+        (Matrix2d::new(1, x.get_cols()), Matrix2d::new(1, y.get_cols()))
+    };
+
+    Some(Centered {
+        x: broadcast_row(x, &x_mean, |a, b| a - b),
+        y: broadcast_row(y, &y_mean, |a, b| a - b),
+        x_mean,
+        y_mean,
+    })
+}
+
+fn finish(c: &Centered, coefficients: Matrix2d) -> Fitted {
+    let intercept = c.y_mean.subtract(&c.x_mean.dot(&coefficients).unwrap()).unwrap();
+    Fitted { coefficients, intercept }
+}
+
+macro_rules! estimator_accessors {
+    () => {
+        pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+            self.fitted.as_ref().and_then(|f| f.predict(x))
+        }
+
+        pub fn score(&self, x: &Matrix2d, y: &Matrix2d) -> Option<f64> {
+            r2_score(y, &self.predict(x)?)
+        }
+
+        // n_features x n_targets
+        pub fn get_coefficients(&self) -> Option<&Matrix2d> {
+            self.fitted.as_ref().map(|f| &f.coefficients)
+        }
+
+        // 1 x n_targets
+        pub fn get_intercept(&self) -> Option<&Matrix2d> {
+            self.fitted.as_ref().map(|f| &f.intercept)
+        }
+    }
+}
+
+pub struct LinearRegression {
+    fit_intercept: bool,
+    fitted: Option<Fitted>,
+}
+
+impl LinearRegression {
+    pub fn new(fit_intercept: bool) -> LinearRegression {
+        LinearRegression { fit_intercept, fitted: None }
+    }
+
+    // None if the shapes disagree or the centered features are rank deficient
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        let c = center(x, y, self.fit_intercept)?;
+        let coefficients = lstsq(&c.x, &c.y)?;
+        self.fitted = Some(finish(&c, coefficients));
+        Some(())
+    }
+
+    estimator_accessors!();
+}
+
+pub struct Ridge {
+    alpha: f64,
+    fit_intercept: bool,
+    fitted: Option<Fitted>,
+}
+
+impl Ridge {
+    pub fn new(alpha: f64, fit_intercept: bool) -> Ridge {
+        Ridge { alpha, fit_intercept, fitted: None }
+    }
+
+    // solves the stacked system [x; sqrt(alpha) I] w = [y; 0] instead of
+    // forming (x^T x + alpha I)
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        let c = center(x, y, self.fit_intercept)?;
+        let (n, p, t) = (x.get_rows(), x.get_cols(), y.get_cols());
+
+        let mut a = flatten(&c.x);
+        let mut b = flatten(&c.y);
+        let penalty = self.alpha.sqrt();
+        for i in 0..p {
+            a.extend((0..p).map(|j| if i == j { penalty } else { 0. }));
+            b.extend((0..t).map(|_| 0.));
+        }
+
+        let (a, b) = (Matrix2d::from_vec_owned(a, n + p, p), Matrix2d::from_vec_owned(b, n + p, t));
+        let coefficients = lstsq(&a, &b)?;
+        self.fitted = Some(finish(&c, coefficients));
+        Some(())
+    }
+
+    estimator_accessors!();
+}
+
+// minimises 1 / (2 n) ||y - x w||^2 + alpha ||w||_1 by cyclic coordinate descent
+pub struct Lasso {
+    alpha: f64,
+    fit_intercept: bool,
+    max_iter: usize,
+    tolerance: f64,
+    n_iter: usize,
+    fitted: Option<Fitted>,
+}
+
+impl Lasso {
+    pub fn new(alpha: f64, fit_intercept: bool) -> Lasso {
+        Lasso::with_params(alpha, fit_intercept, 1000, 1e-6)
+    }
+
+    pub fn with_params(alpha: f64, fit_intercept: bool, max_iter: usize, tolerance: f64) -> Lasso {
+        Lasso {
+            alpha,
+            fit_intercept,
+            max_iter,
+            tolerance,
+            n_iter: 0,
+            fitted: None,
+        }
+    }
+
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        let c = center(x, y, self.fit_intercept)?;
+        let (n, p, t) = (x.get_rows(), x.get_cols(), y.get_cols());
+
+        let cols = (0..p).map(|j| c.x.get_col(j).unwrap()).collect::<Vec<Vec<f64>>>();
+        let col_norms = cols.iter().map(|col| col.iter().map(|v| v * v).sum::<f64>()).collect::<Vec<f64>>();
+        let lambda = self.alpha * n as f64;
+
+        let mut w = vec![0.; p * t];
+        self.n_iter = 0;
+        for target in 0..t {
+            let mut residual = c.y.get_col(target).unwrap();
+
+            for iter in 0..self.max_iter {
+                let mut max_delta = 0f64;
+                let mut max_w = 0f64;
+
+                for j in 0..p {
+                    if col_norms[j] == 0. {
+                        continue;
+                    }
+                    let old = w[j * t + target];
+                    let rho = cols[j].iter().zip(residual.iter()).map(|(a, r)| a * r).sum::<f64>() +
+                              col_norms[j] * old;
+                    let new = soft_threshold(rho, lambda) / col_norms[j];
+
+                    if new != old {
+                        for (r, a) in residual.iter_mut().zip(cols[j].iter()) {
+                            *r -= a * (new - old);
+                        }
+                    }
+                    w[j * t + target] = new;
+                    max_delta = max_delta.max((new - old).abs());
+                    max_w = max_w.max(new.abs());
+                }
+
+                self.n_iter = self.n_iter.max(iter + 1);
+                if max_w == 0. || max_delta / max_w < self.tolerance {
+                    break;
+                }
+            }
+        }
+
+        self.fitted = Some(finish(&c, Matrix2d::from_vec_owned(w, p, t)));
+        Some(())
+    }
+
+    // most sweeps used by any target column in the last fit
+    pub fn get_n_iter(&self) -> usize {
+        self.n_iter
+    }
+
+    estimator_accessors!();
+}
@@ -0,0 +1,128 @@
+use Matrix2d;
+use utils::{sum_vec, flatten, same_shape, sigmoid};
+
+// every loss returns (loss, d_loss / d_pred), averaged over the batch
+
+pub type LossFn = fn(&Matrix2d, &Matrix2d) -> Option<(f64, Matrix2d)>;
+
+const EPSILON: f64 = 1e-12;
+
+fn clip(p: f64) -> f64 {
+    p.clamp(EPSILON, 1. - EPSILON)
+}
+
+fn elementwise<F>(pred: &Matrix2d, target: &Matrix2d, f: F) -> Option<(f64, Matrix2d)>
+    where F: Fn(f64, f64) -> (f64, f64)
+{
+    if !same_shape(pred, target) {
+        return None;
+    }
+
+    let n = (pred.get_rows() * pred.get_cols()) as f64;
+    let (losses, grads): (Vec<f64>, Vec<f64>) = flatten(pred).iter()
+        .zip(flatten(target).iter())
+        .map(|(&p, &t)| {
+            let (l, g) = f(p, t);
+            (l, g / n)
+        })
+        .unzip();
+
+    Some((sum_vec(&losses) / n,
+          Matrix2d::reshape_from_vec(&grads, pred.get_rows(), pred.get_cols()).unwrap()))
+}
+
+pub fn mean_squared_error(pred: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    elementwise(pred, target, |p, t| {
+        let r = p - t;
+        (r * r, 2. * r)
+    })
+}
+
+// `pred` holds probabilities in (0, 1), clipped away from the edges
+pub fn binary_cross_entropy(pred: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    elementwise(pred, target, |p, t| {
+        let p = clip(p);
+        (-(t * p.ln() + (1. - t) * (1. - p).ln()),
+         (p - t) / (p * (1. - p)))
+    })
+}
+
+// max(z, 0) - z * t + ln(1 + e^-|z|) never overflows for large |z|
+pub fn binary_cross_entropy_with_logits(logits: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    elementwise(logits, target, |z, t| {
+        (z.max(0.) - z * t + (-z.abs()).exp().ln_1p(),
+         sigmoid(z) - t)
+    })
+}
+
+// targets are in {-1, 1}
+pub fn hinge(pred: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    elementwise(pred, target, |p, t| {
+        let margin = 1. - t * p;
+        if margin > 0. {
+            (margin, -t)
+        } else {
+            (0., 0.)
+        }
+    })
+}
+
+pub fn huber(pred: &Matrix2d, target: &Matrix2d, delta: f64) -> Option<(f64, Matrix2d)> {
+    elementwise(pred, target, |p, t| {
+        let r = p - t;
+        if r.abs() <= delta {
+            (0.5 * r * r, r)
+        } else {
+            (delta * (r.abs() - 0.5 * delta), delta * r.signum())
+        }
+    })
+}
+
+// rows of `pred` are probability distributions, rows of `target` one-hot (or soft) labels
+pub fn categorical_cross_entropy(pred: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    if !same_shape(pred, target) {
+        return None;
+    }
+
+    let n = pred.get_rows() as f64;
+    let (losses, grads): (Vec<f64>, Vec<f64>) = flatten(pred).iter()
+        .zip(flatten(target).iter())
+        .map(|(&p, &t)| {
+            let p = clip(p);
+            (-t * p.ln(), -t / p / n)
+        })
+        .unzip();
+
+    Some((sum_vec(&losses) / n,
+          Matrix2d::reshape_from_vec(&grads, pred.get_rows(), pred.get_cols()).unwrap()))
+}
+
+// softmax is folded into the loss, using log-sum-exp shifted by the row max
+pub fn categorical_cross_entropy_with_logits(logits: &Matrix2d, target: &Matrix2d) -> Option<(f64, Matrix2d)> {
+    if !same_shape(logits, target) {
+        return None;
+    }
+
+    let n = logits.get_rows() as f64;
+    let mut loss = 0.;
+    let mut grads = Vec::with_capacity(logits.get_rows() * logits.get_cols());
+
+    for row in 0..logits.get_rows() {
+        let z = logits.get_row(row).unwrap();
+        let t = target.get_row(row).unwrap();
+
+        let max = z.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let exps = z.iter().map(|&x| (x - max).exp()).collect::<Vec<f64>>();
+        let sum_exp = sum_vec(&exps);
+        let log_sum_exp = max + sum_exp.ln();
+        let t_sum = sum_vec(&t);
+
+        for i in 0..z.len() {
+            loss += t[i] * (log_sum_exp - z[i]);
+            grads.push((exps[i] / sum_exp * t_sum - t[i]) / n);
+        }
+    }
+
+    Some((loss / n,
+          Matrix2d::reshape_from_vec(&grads, logits.get_rows(), logits.get_cols()).unwrap()))
+}
@@ -0,0 +1,247 @@
+use Matrix2d;
+use loss::categorical_cross_entropy;
+use utils::same_shape;
+
+// Classification metrics take single-column label matrices, as returned by
+// the estimators' `predict`. Undefined ratios (e.g. precision with no
+// positive predictions) count as 0.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Average {
+    // precision/recall/F1 of the given positive label only
+    Binary(f64),
+    // from the pooled counts over all classes
+    Micro,
+    // unweighted mean over classes
+    Macro,
+    // mean over classes weighted by their support in `y_true`
+    Weighted,
+}
+
+fn labels_pair(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<(Vec<f64>, Vec<f64>)> {
+    if y_true.get_cols() != 1 || y_pred.get_cols() != 1 ||
+       y_true.get_rows() != y_pred.get_rows() || y_true.get_rows() == 0 {
+        return None;
+    }
+    Some((y_true.get_col(0).unwrap(), y_pred.get_col(0).unwrap()))
+}
+
+fn ratio(num: f64, denom: f64) -> f64 {
+    if denom == 0. { 0. } else { num / denom }
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    ratio(2. * precision * recall, precision + recall)
+}
+
+// sorted distinct labels appearing in either matrix
+pub fn unique_labels(y_true: &Matrix2d, y_pred: &Matrix2d) -> Vec<f64> {
+    let mut labels = y_true.get_matrix().iter()
+        .chain(y_pred.get_matrix().iter())
+        .cloned()
+        .collect::<Vec<f64>>();
+    labels.sort_by(f64::total_cmp);
+    labels.dedup();
+    labels
+}
+
+pub fn accuracy_score(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<f64> {
+    let (t, p) = labels_pair(y_true, y_pred)?;
+    let hits = t.iter().zip(p.iter()).filter(|&(a, b)| a == b).count();
+    Some(hits as f64 / t.len() as f64)
+}
+
+// rows are true labels, columns predicted labels, both ordered like
+// `unique_labels`; None if any label isn't finite
+pub fn confusion_matrix(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<Matrix2d> {
+    let (t, p) = labels_pair(y_true, y_pred)?;
+    if !t.iter().chain(p.iter()).all(|l| l.is_finite()) {
+        return None;
+    }
+    let labels = unique_labels(y_true, y_pred);
+    let k = labels.len();
+    let index = |l: &f64| labels.iter().position(|x| x == l).unwrap();
+
+    let mut counts = vec![0.; k * k];
+    for (a, b) in t.iter().zip(p.iter()) {
+        counts[index(a) * k + index(b)] += 1.;
+    }
+    Some(Matrix2d::from_vec_owned(counts, k, k))
+}
+
+pub fn precision_recall_f1(y_true: &Matrix2d, y_pred: &Matrix2d, average: Average) -> Option<(f64, f64, f64)> {
+    let cm = confusion_matrix(y_true, y_pred)?;
+    let labels = unique_labels(y_true, y_pred);
+    let k = labels.len();
+
+    let tp = (0..k).map(|i| cm.get_row(i).unwrap()[i]).collect::<Vec<f64>>();
+    let actual = (0..k).map(|i| cm.get_row(i).unwrap().iter().sum()).collect::<Vec<f64>>();
+    let predicted = (0..k).map(|i| cm.get_col(i).unwrap().iter().sum()).collect::<Vec<f64>>();
+
+    let per_class = (0..k)
+        .map(|i| {
+            let p = ratio(tp[i], predicted[i]);
+            let r = ratio(tp[i], actual[i]);
+            (p, r, f1(p, r))
+        })
+        .collect::<Vec<(f64, f64, f64)>>();
+
+    match average {
+        Average::Binary(pos_label) => {
+            Some(match labels.iter().position(|&l| l == pos_label) {
+                Some(i) => per_class[i],
+                None => (0., 0., 0.),
+            })
+        }
+        Average::Micro => {
+            let total_tp = tp.iter().sum::<f64>();
+            let p = ratio(total_tp, predicted.iter().sum());
+            let r = ratio(total_tp, actual.iter().sum());
+            Some((p, r, f1(p, r)))
+        }
+        Average::Macro | Average::Weighted => {
+            // macro averages over the labels present in y_true or y_pred
+            let weights = if average == Average::Macro { vec![1.; k] } else { actual.clone() };
+            let total = weights.iter().sum::<f64>();
+            let mean = |f: &dyn Fn(&(f64, f64, f64)) -> f64| {
+                per_class.iter().zip(weights.iter()).map(|(c, w)| f(c) * w).sum::<f64>() / total
+            };
+            Some((mean(&|c| c.0), mean(&|c| c.1), mean(&|c| c.2)))
+        }
+    }
+}
+
+pub fn precision_score(y_true: &Matrix2d, y_pred: &Matrix2d, average: Average) -> Option<f64> {
+    precision_recall_f1(y_true, y_pred, average).map(|m| m.0)
+}
+
+pub fn recall_score(y_true: &Matrix2d, y_pred: &Matrix2d, average: Average) -> Option<f64> {
+    precision_recall_f1(y_true, y_pred, average).map(|m| m.1)
+}
+
+pub fn f1_score(y_true: &Matrix2d, y_pred: &Matrix2d, average: Average) -> Option<f64> {
+    precision_recall_f1(y_true, y_pred, average).map(|m| m.2)
+}
+
+// (false positive rates, true positive rates, thresholds) for decreasing
+// thresholds over the distinct scores; the first point is (0, 0) at +inf
+pub fn roc_curve(y_true: &Matrix2d, y_score: &Matrix2d, pos_label: f64) -> Option<(Vec<f64>, Vec<f64>, Vec<f64>)> {
+    let (t, s) = labels_pair(y_true, y_score)?;
+    let positives = t.iter().filter(|&&l| l == pos_label).count() as f64;
+    let negatives = t.len() as f64 - positives;
+    if positives == 0. || negatives == 0. {
+        return None;
+    }
+
+    let mut order = (0..t.len()).collect::<Vec<usize>>();
+    order.sort_by(|&a, &b| s[b].total_cmp(&s[a]));
+
+    let (mut fpr, mut tpr, mut thresholds) = (vec![0.], vec![0.], vec![f64::INFINITY]);
+    let (mut tp, mut fp) = (0., 0.);
+    for (i, &idx) in order.iter().enumerate() {
+        if t[idx] == pos_label { tp += 1. } else { fp += 1. }
+        if i + 1 == order.len() || s[order[i + 1]] != s[idx] {
+            fpr.push(fp / negatives);
+            tpr.push(tp / positives);
+            thresholds.push(s[idx]);
+        }
+    }
+    Some((fpr, tpr, thresholds))
+}
+
+// trapezoidal area under a curve given by increasing x
+pub fn auc(x: &[f64], y: &[f64]) -> f64 {
+    x.windows(2)
+        .zip(y.windows(2))
+        .map(|(xs, ys)| (xs[1] - xs[0]) * (ys[0] + ys[1]) / 2.)
+        .sum()
+}
+
+pub fn roc_auc_score(y_true: &Matrix2d, y_score: &Matrix2d, pos_label: f64) -> Option<f64> {
+    let (fpr, tpr, _) = roc_curve(y_true, y_score, pos_label)?;
+    Some(auc(&fpr, &tpr))
+}
+
+// `y_proba` is n x n_classes with columns ordered like the sorted labels of
+// `y_true` (what `predict_proba` returns), or n x 1 holding the probability
+// of the larger of two labels. With a single column `y_true` has to hold
+// both labels, otherwise there's no telling which one `y_proba` is for.
+pub fn log_loss(y_true: &Matrix2d, y_proba: &Matrix2d) -> Option<f64> {
+    if y_true.get_cols() != 1 || y_true.get_rows() != y_proba.get_rows() {
+        return None;
+    }
+
+    let mut classes = y_true.get_col(0).unwrap();
+    if !classes.iter().all(|l| l.is_finite()) {
+        return None;
+    }
+    classes.sort_by(f64::total_cmp);
+    classes.dedup();
+    if y_proba.get_cols() == 1 && classes.len() != 2 {
+        return None;
+    }
+
+    let proba = if y_proba.get_cols() == 1 {
+        let p = y_proba.get_col(0).unwrap();
+        Matrix2d::from_vec_owned(p.iter().flat_map(|&v| vec![1. - v, v]).collect(), p.len(), 2)
+    } else {
+        y_proba.clone()
+    };
+    if classes.len() > proba.get_cols() {
+        return None;
+    }
+
+    let one_hot = y_true.get_col(0).unwrap().iter()
+        .flat_map(|l| {
+            let idx = classes.iter().position(|c| c == l).unwrap();
+            (0..proba.get_cols()).map(move |c| if c == idx { 1. } else { 0. })
+        })
+        .collect();
+    let targets = Matrix2d::from_vec_owned(one_hot, y_true.get_rows(), proba.get_cols());
+    categorical_cross_entropy(&proba, &targets).map(|(l, _)| l)
+}
+
+fn per_output<F>(y_true: &Matrix2d, y_pred: &Matrix2d, f: F) -> Option<f64>
+    where F: Fn(&[f64], &[f64]) -> f64
+{
+    if !same_shape(y_true, y_pred) || y_true.get_rows() == 0 {
+        return None;
+    }
+    let total = (0..y_true.get_cols())
+        .map(|c| f(&y_true.get_col(c).unwrap(), &y_pred.get_col(c).unwrap()))
+        .sum::<f64>();
+    Some(total / y_true.get_cols() as f64)
+}
+
+pub fn mean_absolute_error(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<f64> {
+    per_output(y_true, y_pred, |t, p| {
+        t.iter().zip(p.iter()).map(|(a, b)| (a - b).abs()).sum::<f64>() / t.len() as f64
+    })
+}
+
+pub fn mean_squared_error(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<f64> {
+    per_output(y_true, y_pred, |t, p| {
+        t.iter().zip(p.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>() / t.len() as f64
+    })
+}
+
+pub fn root_mean_squared_error(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<f64> {
+    per_output(y_true, y_pred, |t, p| {
+        (t.iter().zip(p.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>() / t.len() as f64).sqrt()
+    })
+}
+
+// coefficient of determination averaged uniformly over the columns; a
+// constant column scores 1 when predicted exactly and 0 otherwise
+pub fn r2_score(y_true: &Matrix2d, y_pred: &Matrix2d) -> Option<f64> {
+    per_output(y_true, y_pred, |t, p| {
+        let mean = t.iter().sum::<f64>() / t.len() as f64;
+        let ss_res = t.iter().zip(p.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>();
+        let ss_tot = t.iter().map(|a| (a - mean) * (a - mean)).sum::<f64>();
+        if ss_tot == 0. {
+            if ss_res == 0. { 1. } else { 0. }
+        } else {
+            1. - ss_res / ss_tot
+        }
+    })
+}
@@ -0,0 +1,354 @@
+use Matrix2d;
+use tree::encode_labels;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// per query row: neighbour row indices and their distances
+pub type Neighbors = (Vec<Vec<usize>>, Vec<Vec<f64>>);
+
+struct Node {
+    start: usize,
+    end: usize,
+    // bounding box of the points in this node, used to prune searches
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+    children: Option<(usize, usize)>,
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    dist: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.dist.partial_cmp(&other.dist).unwrap_or(Ordering::Equal)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+// Euclidean KD-tree over the rows of a matrix. Points are copied once into a
+// row-major buffer ordered by leaf, so a search only touches the nodes it
+// can't rule out by their bounding boxes.
+pub struct KdTree {
+    n_cols: usize,
+    points: Vec<f64>,
+    indices: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl KdTree {
+    pub fn new(data: &Matrix2d) -> KdTree {
+        KdTree::with_leaf_size(data, 32)
+    }
+
+    pub fn with_leaf_size(data: &Matrix2d, leaf_size: usize) -> KdTree {
+        let n_cols = data.get_cols();
+        let rows = (0..data.get_rows()).map(|r| data.get_row(r).unwrap()).collect::<Vec<Vec<f64>>>();
+        let mut indices = (0..rows.len()).collect::<Vec<usize>>();
+        let mut nodes = Vec::new();
+
+        if !rows.is_empty() {
+            build(&rows, &mut indices, 0, rows.len(), leaf_size.max(1), &mut nodes);
+        }
+
+        let points = indices.iter().flat_map(|&i| rows[i].iter().cloned()).collect();
+        KdTree { n_cols, points, indices, nodes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn point(&self, pos: usize) -> &[f64] {
+        &self.points[pos * self.n_cols..(pos + 1) * self.n_cols]
+    }
+
+    fn squared_dist(&self, pos: usize, q: &[f64]) -> f64 {
+        self.point(pos).iter().zip(q.iter()).map(|(a, b)| (a - b) * (a - b)).sum()
+    }
+
+    fn box_dist(&self, node: &Node, q: &[f64]) -> f64 {
+        q.iter().enumerate()
+            .map(|(d, &v)| {
+                let gap = if v < node.lower[d] {
+                    node.lower[d] - v
+                } else if v > node.upper[d] {
+                    v - node.upper[d]
+                } else {
+                    0.
+                };
+                gap * gap
+            })
+            .sum()
+    }
+
+    fn search_k(&self, node: usize, q: &[f64], k: usize, heap: &mut BinaryHeap<Candidate>) {
+        let n = &self.nodes[node];
+        if heap.len() == k && self.box_dist(n, q) > heap.peek().unwrap().dist {
+            return;
+        }
+
+        match n.children {
+            None => {
+                for pos in n.start..n.end {
+                    let c = Candidate { dist: self.squared_dist(pos, q), index: self.indices[pos] };
+                    if heap.len() < k {
+                        heap.push(c);
+                    } else if c < *heap.peek().unwrap() {
+                        heap.pop();
+                        heap.push(c);
+                    }
+                }
+            }
+            Some((left, right)) => {
+                let (first, second) = if self.box_dist(&self.nodes[left], q) <= self.box_dist(&self.nodes[right], q) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.search_k(first, q, k, heap);
+                self.search_k(second, q, k, heap);
+            }
+        }
+    }
+
+    fn search_radius(&self, node: usize, q: &[f64], r2: f64, out: &mut Vec<Candidate>) {
+        let n = &self.nodes[node];
+        if self.box_dist(n, q) > r2 {
+            return;
+        }
+
+        match n.children {
+            None => {
+                for pos in n.start..n.end {
+                    let dist = self.squared_dist(pos, q);
+                    if dist <= r2 {
+                        out.push(Candidate { dist, index: self.indices[pos] });
+                    }
+                }
+            }
+            Some((left, right)) => {
+                self.search_radius(left, q, r2, out);
+                self.search_radius(right, q, r2, out);
+            }
+        }
+    }
+
+    // for every row of `x`, the row indices and distances of its `k` nearest
+    // points, closest first
+    pub fn query(&self, x: &Matrix2d, k: usize) -> Option<Neighbors> {
+        if x.get_cols() != self.n_cols || k == 0 || k > self.len() {
+            return None;
+        }
+
+        Some((0..x.get_rows())
+            .map(|r| {
+                let q = x.get_row(r).unwrap();
+                let mut heap = BinaryHeap::with_capacity(k + 1);
+                self.search_k(0, &q, k, &mut heap);
+                heap.into_sorted_vec().into_iter().map(|c| (c.index, c.dist.sqrt())).unzip()
+            })
+            .unzip())
+    }
+
+    // for every row of `x`, all points within `radius`, closest first
+    pub fn query_radius(&self, x: &Matrix2d, radius: f64) -> Option<Neighbors> {
+        if x.get_cols() != self.n_cols {
+            return None;
+        }
+
+        Some((0..x.get_rows())
+            .map(|r| {
+                let q = x.get_row(r).unwrap();
+                let mut found = Vec::new();
+                if !self.is_empty() {
+                    self.search_radius(0, &q, radius * radius, &mut found);
+                }
+                found.sort();
+                found.into_iter().map(|c| (c.index, c.dist.sqrt())).unzip()
+            })
+            .unzip())
+    }
+}
+
+fn build(rows: &[Vec<f64>], indices: &mut [usize], start: usize, end: usize,
+         leaf_size: usize, nodes: &mut Vec<Node>) -> usize {
+    let n_cols = rows[0].len();
+    let mut lower = vec![f64::INFINITY; n_cols];
+    let mut upper = vec![f64::NEG_INFINITY; n_cols];
+    for &i in indices[start..end].iter() {
+        for d in 0..n_cols {
+            lower[d] = lower[d].min(rows[i][d]);
+            upper[d] = upper[d].max(rows[i][d]);
+        }
+    }
+
+    // split at the median of the widest dimension
+    let dim = (0..n_cols).fold(0, |b, d| if upper[d] - lower[d] > upper[b] - lower[b] { d } else { b });
+
+    let id = nodes.len();
+    nodes.push(Node { start, end, lower, upper, children: None });
+    if end - start <= leaf_size {
+        return id;
+    }
+
+    let mid = (end - start) / 2;
+    indices[start..end].select_nth_unstable_by(mid, |&a, &b| {
+        rows[a][dim].partial_cmp(&rows[b][dim]).unwrap_or(Ordering::Equal)
+    });
+
+    let left = build(rows, indices, start, start + mid, leaf_size, nodes);
+    let right = build(rows, indices, start + mid, end, leaf_size, nodes);
+    nodes[id].children = Some((left, right));
+    id
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weights {
+    Uniform,
+    // inverse distance; exact matches take all the weight
+    Distance,
+}
+
+fn neighbor_weights(distances: &[f64], weights: Weights) -> Vec<f64> {
+    match weights {
+        Weights::Uniform => vec![1.; distances.len()],
+        Weights::Distance => {
+            if distances.contains(&0.) {
+                distances.iter().map(|&d| if d == 0. { 1. } else { 0. }).collect()
+            } else {
+                distances.iter().map(|d| 1. / d).collect()
+            }
+        }
+    }
+}
+
+pub struct KNeighborsClassifier {
+    n_neighbors: usize,
+    weights: Weights,
+    tree: Option<KdTree>,
+    // each training row's index into `classes`
+    labels: Vec<usize>,
+    classes: Vec<f64>,
+}
+
+impl KNeighborsClassifier {
+    pub fn new(n_neighbors: usize, weights: Weights) -> KNeighborsClassifier {
+        KNeighborsClassifier {
+            n_neighbors,
+            weights,
+            tree: None,
+            labels: Vec::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    // `y` is a single column of class labels, None if any isn't finite
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() < self.n_neighbors {
+            return None;
+        }
+        let (classes, encoded) = encode_labels(y)?;
+        self.labels = encoded.iter().map(|&c| c as usize).collect();
+        self.classes = classes;
+        self.tree = Some(KdTree::new(x));
+        Some(())
+    }
+
+    // n x n_classes of weighted votes, columns ordered like `get_classes`
+    pub fn predict_proba(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let (indices, distances) = self.tree.as_ref()?.query(x, self.n_neighbors)?;
+        let n_classes = self.classes.len();
+
+        let mut out = Vec::with_capacity(x.get_rows() * n_classes);
+        for (idx, dist) in indices.iter().zip(distances.iter()) {
+            let mut votes = vec![0.; n_classes];
+            for (&i, w) in idx.iter().zip(neighbor_weights(dist, self.weights)) {
+                votes[self.labels[i]] += w;
+            }
+            let total = votes.iter().sum::<f64>();
+            out.extend(votes.iter().map(|v| v / total));
+        }
+        Some(Matrix2d::from_vec_owned(out, x.get_rows(), n_classes))
+    }
+
+    // n x 1 of predicted labels, ties going to the smaller label
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let proba = self.predict_proba(x)?;
+        let labels = (0..proba.get_rows())
+            .map(|r| {
+                let row = proba.get_row(r).unwrap();
+                let best = (0..row.len()).fold(0, |b, i| if row[i] > row[b] { i } else { b });
+                self.classes[best]
+            })
+            .collect();
+        Some(Matrix2d::from_vec_owned(labels, x.get_rows(), 1))
+    }
+
+    pub fn get_classes(&self) -> &[f64] {
+        &self.classes
+    }
+}
+
+pub struct KNeighborsRegressor {
+    n_neighbors: usize,
+    weights: Weights,
+    tree: Option<KdTree>,
+    targets: Option<Matrix2d>,
+}
+
+impl KNeighborsRegressor {
+    pub fn new(n_neighbors: usize, weights: Weights) -> KNeighborsRegressor {
+        KNeighborsRegressor {
+            n_neighbors,
+            weights,
+            tree: None,
+            targets: None,
+        }
+    }
+
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || x.get_rows() < self.n_neighbors {
+            return None;
+        }
+        self.tree = Some(KdTree::new(x));
+        self.targets = Some(y.clone());
+        Some(())
+    }
+
+    // weighted mean of the neighbours' target rows
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        let (indices, distances) = self.tree.as_ref()?.query(x, self.n_neighbors)?;
+        let targets = self.targets.as_ref().unwrap();
+        let n_targets = targets.get_cols();
+
+        let mut out = Vec::with_capacity(x.get_rows() * n_targets);
+        for (idx, dist) in indices.iter().zip(distances.iter()) {
+            let w = neighbor_weights(dist, self.weights);
+            let total = w.iter().sum::<f64>();
+            let mut row = vec![0.; n_targets];
+            for (&i, wi) in idx.iter().zip(w.iter()) {
+                for (acc, t) in row.iter_mut().zip(targets.get_row(i).unwrap()) {
+                    *acc += wi * t / total;
+                }
+            }
+            out.extend(row);
+        }
+        Some(Matrix2d::from_vec_owned(out, x.get_rows(), n_targets))
+    }
+}
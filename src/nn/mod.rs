@@ -0,0 +1,285 @@
+use Matrix2d;
+use loss::LossFn;
+use optim::{Optimizer, Sgd};
+use utils::{sigmoid, sum_rows};
+
+use rand::distributions::{IndependentSample, Range};
+use rand::{Rng, SeedableRng, StdRng};
+use rand;
+
+pub trait Layer {
+    // caches whatever `backward` needs from the last forward pass,
+    // None if the input doesn't fit the layer
+    fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d>;
+
+    // takes d_loss / d_output, returns d_loss / d_input
+    fn backward(&mut self, grad: &Matrix2d) -> Matrix2d;
+
+    fn params_and_grads(&mut self) -> Vec<(&mut Matrix2d, &Matrix2d)> {
+        Vec::new()
+    }
+}
+
+fn add_row(m: &Matrix2d, row: &Matrix2d) -> Matrix2d {
+    let bias = row.get_row(0).unwrap();
+    let vec = (0..m.get_rows())
+        .flat_map(|r| {
+            m.get_row(r).unwrap().iter()
+                .zip(bias.iter())
+                .map(|(x, b)| x + b)
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, m.get_rows(), m.get_cols())
+}
+
+fn hstack(a: &Matrix2d, b: &Matrix2d) -> Matrix2d {
+    let vec = (0..a.get_rows())
+        .flat_map(|r| {
+            let mut row = a.get_row(r).unwrap();
+            row.extend(b.get_row(r).unwrap());
+            row
+        })
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, a.get_rows(), a.get_cols() + b.get_cols())
+}
+
+// the rows of `m` in a seeded random order, each exactly once
+fn permute_rows(m: &Matrix2d, seed: usize) -> Matrix2d {
+    let mut rng: StdRng = StdRng::from_seed(&[seed][..]);
+    let mut order = (0..m.get_rows()).collect::<Vec<usize>>();
+    // Fisher-Yates
+    for i in (1..order.len()).rev() {
+        order.swap(i, rng.gen_range(0, i + 1));
+    }
+    let vec = order.iter()
+        .flat_map(|&r| m.get_row(r).unwrap())
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, m.get_rows(), m.get_cols())
+}
+
+fn split_cols(m: &Matrix2d, at: usize) -> (Matrix2d, Matrix2d) {
+    let mut left = Vec::with_capacity(m.get_rows() * at);
+    let mut right = Vec::with_capacity(m.get_rows() * (m.get_cols() - at));
+    for r in 0..m.get_rows() {
+        let row = m.get_row(r).unwrap();
+        left.extend_from_slice(&row[..at]);
+        right.extend_from_slice(&row[at..]);
+    }
+    (Matrix2d::from_vec_owned(left, m.get_rows(), at),
+     Matrix2d::from_vec_owned(right, m.get_rows(), m.get_cols() - at))
+}
+
+pub struct Dense {
+    weights: Matrix2d,
+    bias: Matrix2d,
+    grad_weights: Matrix2d,
+    grad_bias: Matrix2d,
+    input: Option<Matrix2d>,
+}
+
+impl Dense {
+    // Glorot uniform weights, zero bias
+    pub fn new(n_inputs: usize, n_outputs: usize) -> Dense {
+        let limit = (6. / (n_inputs + n_outputs) as f64).sqrt();
+        let sample = Range::new(-limit, limit);
+        let mut rng = rand::thread_rng();
+        let weights = (0..n_inputs * n_outputs)
+            .map(|_| sample.ind_sample(&mut rng))
+            .collect::<Vec<f64>>();
+
+        Dense::from_weights(Matrix2d::from_vec_owned(weights, n_inputs, n_outputs),
+                            Matrix2d::new(1, n_outputs)).unwrap()
+    }
+
+    pub fn from_weights(weights: Matrix2d, bias: Matrix2d) -> Option<Dense> {
+        if bias.get_rows() != 1 || bias.get_cols() != weights.get_cols() {
+            return None;
+        }
+        Some(Dense {
+            grad_weights: Matrix2d::new(weights.get_rows(), weights.get_cols()),
+            grad_bias: Matrix2d::new(1, bias.get_cols()),
+            weights,
+            bias,
+            input: None,
+        })
+    }
+
+    pub fn get_weights(&self) -> &Matrix2d {
+        &self.weights
+    }
+
+    pub fn get_bias(&self) -> &Matrix2d {
+        &self.bias
+    }
+}
+
+impl Layer for Dense {
+    fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        let out = add_row(&input.dot(&self.weights)?, &self.bias);
+        self.input = Some(input.clone());
+        Some(out)
+    }
+
+    fn backward(&mut self, grad: &Matrix2d) -> Matrix2d {
+        let input = self.input.as_ref().expect("Dense::backward called before forward");
+        self.grad_weights = input.transpose().dot(grad).unwrap();
+        self.grad_bias = sum_rows(grad);
+        grad.dot(&self.weights.transpose()).unwrap()
+    }
+
+    fn params_and_grads(&mut self) -> Vec<(&mut Matrix2d, &Matrix2d)> {
+        vec![(&mut self.weights, &self.grad_weights),
+             (&mut self.bias, &self.grad_bias)]
+    }
+}
+
+#[derive(Default)]
+pub struct Relu {
+    input: Option<Matrix2d>,
+}
+
+#[derive(Default)]
+pub struct Sigmoid {
+    output: Option<Matrix2d>,
+}
+
+#[derive(Default)]
+pub struct Tanh {
+    output: Option<Matrix2d>,
+}
+
+impl Relu {
+    pub fn new() -> Relu {
+        Relu { input: None }
+    }
+}
+
+impl Sigmoid {
+    pub fn new() -> Sigmoid {
+        Sigmoid { output: None }
+    }
+}
+
+impl Tanh {
+    pub fn new() -> Tanh {
+        Tanh { output: None }
+    }
+}
+
+impl Layer for Relu {
+    fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        self.input = Some(input.clone());
+        Some(input.apply_fn(|x| x.max(0.)))
+    }
+
+    fn backward(&mut self, grad: &Matrix2d) -> Matrix2d {
+        let input = self.input.as_ref().expect("Relu::backward called before forward");
+        grad.mult(&input.apply_fn(|x| if x > 0. { 1. } else { 0. })).unwrap()
+    }
+}
+
+impl Layer for Sigmoid {
+    fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        let out = input.apply_fn(sigmoid);
+        self.output = Some(out.clone());
+        Some(out)
+    }
+
+    fn backward(&mut self, grad: &Matrix2d) -> Matrix2d {
+        let out = self.output.as_ref().expect("Sigmoid::backward called before forward");
+        grad.mult(&out.apply_fn(|s| s * (1. - s))).unwrap()
+    }
+}
+
+impl Layer for Tanh {
+    fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        let out = input.apply_fn(f64::tanh);
+        self.output = Some(out.clone());
+        Some(out)
+    }
+
+    fn backward(&mut self, grad: &Matrix2d) -> Matrix2d {
+        let out = self.output.as_ref().expect("Tanh::backward called before forward");
+        grad.mult(&out.apply_fn(|t| 1. - t * t)).unwrap()
+    }
+}
+
+pub struct Sequential {
+    layers: Vec<Box<dyn Layer>>,
+    loss: LossFn,
+    optimizer: Box<dyn Optimizer>,
+}
+
+impl Sequential {
+    pub fn new(loss: LossFn, learning_rate: f64) -> Sequential {
+        Sequential::with_optimizer(loss, Box::new(Sgd::new(learning_rate)))
+    }
+
+    pub fn with_optimizer(loss: LossFn, optimizer: Box<dyn Optimizer>) -> Sequential {
+        Sequential {
+            layers: Vec::new(),
+            loss,
+            optimizer,
+        }
+    }
+
+    pub fn get_optimizer_mut(&mut self) -> &mut dyn Optimizer {
+        &mut *self.optimizer
+    }
+
+    pub fn add(&mut self, layer: Box<dyn Layer>) {
+        self.layers.push(layer);
+    }
+
+    pub fn forward(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        self.layers.iter_mut().try_fold(input.clone(), |x, layer| layer.forward(&x))
+    }
+
+    pub fn backward(&mut self, grad: &Matrix2d) -> Matrix2d {
+        self.layers.iter_mut().rev().fold(grad.clone(), |g, layer| layer.backward(&g))
+    }
+
+    pub fn predict(&mut self, input: &Matrix2d) -> Option<Matrix2d> {
+        self.forward(input)
+    }
+
+    // one gradient step on a single batch, returns the batch loss
+    pub fn train_batch(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<f64> {
+        let out = self.forward(x)?;
+        let (loss, grad) = (self.loss)(&out, y)?;
+        self.backward(&grad);
+
+        let mut params = Vec::new();
+        let mut grads = Vec::new();
+        for layer in self.layers.iter_mut() {
+            for (param, grad) in layer.params_and_grads() {
+                grads.push(grad.clone());
+                params.push(param);
+            }
+        }
+        self.optimizer.step(&mut params, &grads)?;
+        Some(loss)
+    }
+
+    // x and y are permuted together each epoch, seeded by the epoch number,
+    // so every row is seen once per epoch. Returns the mean loss per epoch.
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d, epochs: usize, batch_size: usize) -> Option<Vec<f64>> {
+        if x.get_rows() != y.get_rows() || batch_size == 0 {
+            return None;
+        }
+
+        let joined = hstack(x, y);
+        let mut history = Vec::with_capacity(epochs);
+        for epoch in 0..epochs {
+            let batches = permute_rows(&joined, epoch + 1).mini_batch(batch_size);
+            let mut total = 0.;
+            for batch in batches.iter() {
+                let (bx, by) = split_cols(batch, x.get_cols());
+                total += self.train_batch(&bx, &by)?;
+            }
+            history.push(total / batches.len() as f64);
+        }
+        Some(history)
+    }
+}
@@ -0,0 +1,336 @@
+use Matrix2d;
+use utils::frobenius_norm;
+
+use std::f64::consts::PI;
+
+// Optimizers keep one state buffer per parameter, matched up by position, so
+// `params` must be passed in the same order on every step.
+pub trait Optimizer {
+    // None, leaving every parameter untouched, if `grads` doesn't match
+    // `params` one to one in shape
+    fn step(&mut self, params: &mut [&mut Matrix2d], grads: &[Matrix2d]) -> Option<()>;
+    fn get_learning_rate(&self) -> f64;
+    fn set_learning_rate(&mut self, learning_rate: f64);
+}
+
+pub trait LrSchedule {
+    fn get_learning_rate(&self, epoch: usize) -> f64;
+}
+
+fn check_shapes(params: &[&mut Matrix2d], grads: &[Matrix2d]) -> Option<()> {
+    let matches = params.len() == grads.len() && params.iter().zip(grads.iter())
+        .all(|(p, g)| p.get_rows() == g.get_rows() && p.get_cols() == g.get_cols());
+    if !matches {
+        return None;
+    }
+    Some(())
+}
+
+// grad values laid out like `param`'s buffer, whatever the strides of either
+fn in_layout(grad: &Matrix2d, param: &Matrix2d) -> Vec<f64> {
+    if grad.get_row_stride() == param.get_row_stride() &&
+       grad.get_col_stride() == param.get_col_stride() {
+        return grad.get_matrix().clone();
+    }
+
+    let mut out = vec![0.; param.get_matrix().len()];
+    for row in 0..grad.get_rows() {
+        for (col, g) in grad.get_row(row).unwrap().into_iter().enumerate() {
+            out[row * param.get_row_stride() + col * param.get_col_stride()] = g;
+        }
+    }
+    out
+}
+
+fn init_state(state: &mut Vec<Vec<f64>>, params: &[&mut Matrix2d]) {
+    if state.len() != params.len() {
+        *state = params.iter().map(|p| vec![0.; p.get_matrix().len()]).collect();
+    }
+}
+
+pub struct Sgd {
+    learning_rate: f64,
+    momentum: f64,
+    nesterov: bool,
+    velocity: Vec<Vec<f64>>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64) -> Sgd {
+        Sgd::with_momentum(learning_rate, 0., false)
+    }
+
+    pub fn with_momentum(learning_rate: f64, momentum: f64, nesterov: bool) -> Sgd {
+        Sgd {
+            learning_rate,
+            momentum,
+            nesterov,
+            velocity: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, params: &mut [&mut Matrix2d], grads: &[Matrix2d]) -> Option<()> {
+        check_shapes(params, grads)?;
+        init_state(&mut self.velocity, params);
+
+        for (i, param) in params.iter_mut().enumerate() {
+            let g = in_layout(&grads[i], param);
+            let v = &mut self.velocity[i];
+            let p = param.get_matrix_mut();
+
+            for j in 0..p.len() {
+                v[j] = self.momentum * v[j] + g[j];
+                let update = if self.nesterov { g[j] + self.momentum * v[j] } else { v[j] };
+                p[j] -= self.learning_rate * update;
+            }
+        }
+        Some(())
+    }
+
+    fn get_learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+pub struct RmsProp {
+    learning_rate: f64,
+    rho: f64,
+    epsilon: f64,
+    square_avg: Vec<Vec<f64>>,
+}
+
+impl RmsProp {
+    pub fn new(learning_rate: f64) -> RmsProp {
+        RmsProp::with_params(learning_rate, 0.9, 1e-8)
+    }
+
+    pub fn with_params(learning_rate: f64, rho: f64, epsilon: f64) -> RmsProp {
+        RmsProp {
+            learning_rate,
+            rho,
+            epsilon,
+            square_avg: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn step(&mut self, params: &mut [&mut Matrix2d], grads: &[Matrix2d]) -> Option<()> {
+        check_shapes(params, grads)?;
+        init_state(&mut self.square_avg, params);
+
+        for (i, param) in params.iter_mut().enumerate() {
+            let g = in_layout(&grads[i], param);
+            let s = &mut self.square_avg[i];
+            let p = param.get_matrix_mut();
+
+            for j in 0..p.len() {
+                s[j] = self.rho * s[j] + (1. - self.rho) * g[j] * g[j];
+                p[j] -= self.learning_rate * g[j] / (s[j].sqrt() + self.epsilon);
+            }
+        }
+        Some(())
+    }
+
+    fn get_learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+pub struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    t: i32,
+    m: Vec<Vec<f64>>,
+    v: Vec<Vec<f64>>,
+}
+
+impl Adam {
+    pub fn new(learning_rate: f64) -> Adam {
+        Adam::with_params(learning_rate, 0.9, 0.999, 1e-8)
+    }
+
+    pub fn with_params(learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64) -> Adam {
+        Adam {
+            learning_rate,
+            beta1,
+            beta2,
+            epsilon,
+            t: 0,
+            m: Vec::new(),
+            v: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, params: &mut [&mut Matrix2d], grads: &[Matrix2d]) -> Option<()> {
+        check_shapes(params, grads)?;
+        init_state(&mut self.m, params);
+        init_state(&mut self.v, params);
+        self.t += 1;
+
+        let m_correction = 1. - self.beta1.powi(self.t);
+        let v_correction = 1. - self.beta2.powi(self.t);
+
+        for (i, param) in params.iter_mut().enumerate() {
+            let g = in_layout(&grads[i], param);
+            let m = &mut self.m[i];
+            let v = &mut self.v[i];
+            let p = param.get_matrix_mut();
+
+            for j in 0..p.len() {
+                m[j] = self.beta1 * m[j] + (1. - self.beta1) * g[j];
+                v[j] = self.beta2 * v[j] + (1. - self.beta2) * g[j] * g[j];
+                let m_hat = m[j] / m_correction;
+                let v_hat = v[j] / v_correction;
+                p[j] -= self.learning_rate * m_hat / (v_hat.sqrt() + self.epsilon);
+            }
+        }
+        Some(())
+    }
+
+    fn get_learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+// Adam with weight decay applied straight to the weights instead of the gradient
+pub struct AdamW {
+    adam: Adam,
+    weight_decay: f64,
+}
+
+impl AdamW {
+    pub fn new(learning_rate: f64, weight_decay: f64) -> AdamW {
+        AdamW {
+            adam: Adam::new(learning_rate),
+            weight_decay,
+        }
+    }
+
+    pub fn with_params(learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64, weight_decay: f64) -> AdamW {
+        AdamW {
+            adam: Adam::with_params(learning_rate, beta1, beta2, epsilon),
+            weight_decay,
+        }
+    }
+}
+
+impl Optimizer for AdamW {
+    fn step(&mut self, params: &mut [&mut Matrix2d], grads: &[Matrix2d]) -> Option<()> {
+        check_shapes(params, grads)?;
+        let decay = 1. - self.adam.learning_rate * self.weight_decay;
+        for param in params.iter_mut() {
+            for x in param.get_matrix_mut().iter_mut() {
+                *x *= decay;
+            }
+        }
+        self.adam.step(params, grads)
+    }
+
+    fn get_learning_rate(&self) -> f64 {
+        self.adam.get_learning_rate()
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.adam.set_learning_rate(learning_rate);
+    }
+}
+
+pub struct StepDecay {
+    initial: f64,
+    gamma: f64,
+    step_size: usize,
+}
+
+impl StepDecay {
+    // multiplies the rate by `gamma` every `step_size` epochs, None if
+    // `step_size` is 0
+    pub fn new(initial: f64, gamma: f64, step_size: usize) -> Option<StepDecay> {
+        if step_size == 0 {
+            return None;
+        }
+        Some(StepDecay { initial, gamma, step_size })
+    }
+}
+
+impl LrSchedule for StepDecay {
+    fn get_learning_rate(&self, epoch: usize) -> f64 {
+        self.initial * self.gamma.powi((epoch / self.step_size) as i32)
+    }
+}
+
+pub struct ExponentialDecay {
+    initial: f64,
+    gamma: f64,
+}
+
+impl ExponentialDecay {
+    pub fn new(initial: f64, gamma: f64) -> ExponentialDecay {
+        ExponentialDecay { initial, gamma }
+    }
+}
+
+impl LrSchedule for ExponentialDecay {
+    fn get_learning_rate(&self, epoch: usize) -> f64 {
+        self.initial * self.gamma.powi(epoch as i32)
+    }
+}
+
+pub struct CosineAnnealing {
+    initial: f64,
+    minimum: f64,
+    period: usize,
+}
+
+impl CosineAnnealing {
+    // anneals from `initial` down to `minimum` over `period` epochs, then
+    // stays there. None if `period` is 0.
+    pub fn new(initial: f64, minimum: f64, period: usize) -> Option<CosineAnnealing> {
+        if period == 0 {
+            return None;
+        }
+        Some(CosineAnnealing { initial, minimum, period })
+    }
+}
+
+impl LrSchedule for CosineAnnealing {
+    fn get_learning_rate(&self, epoch: usize) -> f64 {
+        let progress = epoch.min(self.period) as f64 / self.period as f64;
+        self.minimum + 0.5 * (self.initial - self.minimum) * (1. + (PI * progress).cos())
+    }
+}
+
+// rescales `grads` in place so their combined norm is at most `max_norm`,
+// returns the norm before clipping
+pub fn clip_grad_norm(grads: &mut [Matrix2d], max_norm: f64) -> f64 {
+    let total = grads.iter()
+        .map(|g| frobenius_norm(g).powi(2))
+        .sum::<f64>()
+        .sqrt();
+
+    if total > max_norm {
+        let factor = max_norm / total;
+        for g in grads.iter_mut() {
+            *g = g.scale(factor);
+        }
+    }
+    total
+}
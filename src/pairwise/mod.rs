@@ -0,0 +1,103 @@
+use Matrix2d;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Euclidean,
+    SquaredEuclidean,
+    Manhattan,
+    Cosine,
+    Chebyshev,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    Linear,
+    // exp(-gamma |a - b|^2)
+    Rbf { gamma: f64 },
+    // (gamma <a, b> + coef0)^degree
+    Polynomial { gamma: f64, coef0: f64, degree: i32 },
+    // tanh(gamma <a, b> + coef0)
+    Sigmoid { gamma: f64, coef0: f64 },
+}
+
+fn row_norms(m: &Matrix2d) -> Vec<f64> {
+    (0..m.get_rows())
+        .map(|r| m.get_row(r).unwrap().iter().map(|x| x * x).sum())
+        .collect()
+}
+
+// maps every element of the n x m product a b^T through f(a_i . b_j, i, j)
+fn map_gram<F>(a: &Matrix2d, b: &Matrix2d, f: F) -> Matrix2d
+    where F: Fn(f64, usize, usize) -> f64
+{
+    let gram = a.dot(&b.transpose()).unwrap();
+    let vec = (0..gram.get_rows())
+        .flat_map(|i| {
+            gram.get_row(i).unwrap().iter()
+                .enumerate()
+                .map(|(j, &g)| f(g, i, j))
+                .collect::<Vec<f64>>()
+        })
+        .collect();
+    Matrix2d::from_vec_owned(vec, a.get_rows(), b.get_rows())
+}
+
+// |a|^2 + |b|^2 - 2 a b^T, clamped at zero against cancellation
+fn squared_euclidean(a: &Matrix2d, b: &Matrix2d) -> Matrix2d {
+    let (an, bn) = (row_norms(a), row_norms(b));
+    map_gram(a, b, |g, i, j| (an[i] + bn[j] - 2. * g).max(0.))
+}
+
+fn elementwise<F>(a: &Matrix2d, b: &Matrix2d, f: F) -> Matrix2d
+    where F: Fn(&[f64], &[f64]) -> f64
+{
+    let b_rows = (0..b.get_rows()).map(|j| b.get_row(j).unwrap()).collect::<Vec<Vec<f64>>>();
+    let vec = (0..a.get_rows())
+        .flat_map(|i| {
+            let row = a.get_row(i).unwrap();
+            b_rows.iter().map(|other| f(&row, other)).collect::<Vec<f64>>()
+        })
+        .collect();
+    Matrix2d::from_vec_owned(vec, a.get_rows(), b.get_rows())
+}
+
+// n x m matrix of distances between the rows of `a` and the rows of `b`,
+// None if they have a different number of columns
+pub fn pairwise_distances(a: &Matrix2d, b: &Matrix2d, metric: Metric) -> Option<Matrix2d> {
+    if a.get_cols() != b.get_cols() {
+        return None;
+    }
+
+    Some(match metric {
+        Metric::SquaredEuclidean => squared_euclidean(a, b),
+        Metric::Euclidean => squared_euclidean(a, b).apply_fn(f64::sqrt),
+        Metric::Cosine => {
+            let an = row_norms(a).iter().map(|x| x.sqrt()).collect::<Vec<f64>>();
+            let bn = row_norms(b).iter().map(|x| x.sqrt()).collect::<Vec<f64>>();
+            map_gram(a, b, |g, i, j| {
+                let denom = an[i] * bn[j];
+                if denom == 0. { 1. } else { 1. - g / denom }
+            })
+        }
+        Metric::Manhattan => elementwise(a, b, |x, y| {
+            x.iter().zip(y.iter()).map(|(p, q)| (p - q).abs()).sum()
+        }),
+        Metric::Chebyshev => elementwise(a, b, |x, y| {
+            x.iter().zip(y.iter()).fold(0., |m, (p, q)| f64::max(m, (p - q).abs()))
+        }),
+    })
+}
+
+// n x m Gram matrix k(a_i, b_j)
+pub fn pairwise_kernels(a: &Matrix2d, b: &Matrix2d, kernel: Kernel) -> Option<Matrix2d> {
+    if a.get_cols() != b.get_cols() {
+        return None;
+    }
+
+    Some(match kernel {
+        Kernel::Linear => map_gram(a, b, |g, _, _| g),
+        Kernel::Rbf { gamma } => squared_euclidean(a, b).apply_fn(|d| (-gamma * d).exp()),
+        Kernel::Polynomial { gamma, coef0, degree } => map_gram(a, b, |g, _, _| (gamma * g + coef0).powi(degree)),
+        Kernel::Sigmoid { gamma, coef0 } => map_gram(a, b, |g, _, _| (gamma * g + coef0).tanh()),
+    })
+}
@@ -0,0 +1,620 @@
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Explicit SIMD kernels for f32 and f64 slices. The instruction set is
+// picked at runtime, the widest of SSE2, AVX2 and AVX-512 the cpu has, with
+// a scalar fallback elsewhere. Elementwise results (add, sub, mul, div,
+// scale) are bit-identical at every level. `sum` keeps the eight running
+// sums of `utils::unrolled_sum` and combines them in the same order, so it
+// rounds like it on every level too; `exp` is within a few ulp of the std
+// one, which it falls back to outside [EXP_LO, EXP_HI] and for NaN.
+//
+// `Matrix2d::addition`, `subtract`, `mult` and `scale` and
+// `utils::unrolled_sum` run on these kernels.
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Level {
+    Scalar,
+    Sse2,
+    Avx2,
+    Avx512,
+}
+
+impl Level {
+    fn from_usize(level: usize) -> Level {
+        match level {
+            1 => Level::Scalar,
+            2 => Level::Sse2,
+            3 => Level::Avx2,
+            _ => Level::Avx512,
+        }
+    }
+
+    fn to_usize(self) -> usize {
+        self as usize + 1
+    }
+}
+
+// 0 until detected
+static DETECTED: AtomicUsize = AtomicUsize::new(0);
+// 0 uses the detected level
+static FORCED: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> Level {
+    if is_x86_feature_detected!("avx512f") {
+        Level::Avx512
+    } else if is_x86_feature_detected!("avx2") {
+        Level::Avx2
+    } else {
+        // part of x86_64 itself
+        Level::Sse2
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> Level {
+    Level::Scalar
+}
+
+// the widest level this cpu supports
+pub fn detected_level() -> Level {
+    match DETECTED.load(Ordering::Relaxed) {
+        0 => {
+            let level = detect();
+            DETECTED.store(level.to_usize(), Ordering::Relaxed);
+            level
+        }
+        level => Level::from_usize(level),
+    }
+}
+
+// Caps the level the kernels use, e.g. to compare them; None goes back to
+// the detected one. Levels above it are never used.
+pub fn set_level(level: Option<Level>) {
+    FORCED.store(level.map_or(0, |l| l.to_usize()), Ordering::Relaxed);
+}
+
+// the level the kernels currently run at
+pub fn level() -> Level {
+    let detected = detected_level();
+    match FORCED.load(Ordering::Relaxed) {
+        0 => detected,
+        forced if Level::from_usize(forced) < detected => Level::from_usize(forced),
+        _ => detected,
+    }
+}
+
+// f32 or f64, along with the constants the kernels need
+pub trait Element: Copy + Default + PartialOrd + Sync + 'static
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+    const NEG_INFINITY: Self;
+    // `exp` is vectorized on [EXP_LO, EXP_HI]
+    const EXP_LO: Self;
+    const EXP_HI: Self;
+
+    fn exp(self) -> Self;
+
+    fn kernels(level: Level) -> &'static Kernels<Self>;
+}
+
+// one function per operation for a given type and level
+pub struct Kernels<T: 'static> {
+    add: unsafe fn(&[T], &[T], &mut [T]),
+    sub: unsafe fn(&[T], &[T], &mut [T]),
+    mul: unsafe fn(&[T], &[T], &mut [T]),
+    div: unsafe fn(&[T], &[T], &mut [T]),
+    scale: unsafe fn(&[T], T, &mut [T]),
+    exp: unsafe fn(&[T], &mut [T]),
+    sum: unsafe fn(&[T]) -> T,
+    max: unsafe fn(&[T]) -> T,
+}
+
+// dst = u + v, over the shortest of the three
+pub fn add<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+    debug_assert!(u.len() == v.len() && u.len() == dst.len());
+    unsafe { (T::kernels(level()).add)(u, v, dst) }
+}
+
+pub fn sub<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+    debug_assert!(u.len() == v.len() && u.len() == dst.len());
+    unsafe { (T::kernels(level()).sub)(u, v, dst) }
+}
+
+pub fn mul<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+    debug_assert!(u.len() == v.len() && u.len() == dst.len());
+    unsafe { (T::kernels(level()).mul)(u, v, dst) }
+}
+
+pub fn div<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+    debug_assert!(u.len() == v.len() && u.len() == dst.len());
+    unsafe { (T::kernels(level()).div)(u, v, dst) }
+}
+
+// dst = k * u
+pub fn scale<T: Element>(u: &[T], k: T, dst: &mut [T]) {
+    debug_assert_eq!(u.len(), dst.len());
+    unsafe { (T::kernels(level()).scale)(u, k, dst) }
+}
+
+pub fn exp<T: Element>(u: &[T], dst: &mut [T]) {
+    debug_assert_eq!(u.len(), dst.len());
+    unsafe { (T::kernels(level()).exp)(u, dst) }
+}
+
+pub fn sum<T: Element>(xs: &[T]) -> T {
+    unsafe { (T::kernels(level()).sum)(xs) }
+}
+
+// largest element ignoring NaNs, -inf for an empty slice
+pub fn max<T: Element>(xs: &[T]) -> T {
+    unsafe { (T::kernels(level()).max)(xs) }
+}
+
+// Adds up the running sums the way `utils::unrolled_sum` always has, then
+// the tail of fewer than eight elements.
+fn finish_sum<T: Element>(p: &[T; 8], tail: &[T]) -> T {
+    let mut sum = T::default();
+    for j in 0..4 {
+        sum = sum + (p[j] + p[j + 4]);
+    }
+    tail.iter().fold(sum, |s, &x| s + x)
+}
+
+mod scalar {
+    use super::{Element, finish_sum};
+
+    fn zip<T: Element, F: Fn(T, T) -> T>(u: &[T], v: &[T], dst: &mut [T], f: F) {
+        for ((d, &x), &y) in dst.iter_mut().zip(u.iter()).zip(v.iter()) {
+            *d = f(x, y);
+        }
+    }
+
+    pub unsafe fn add<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+        zip(u, v, dst, |x, y| x + y)
+    }
+
+    pub unsafe fn sub<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+        zip(u, v, dst, |x, y| x - y)
+    }
+
+    pub unsafe fn mul<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+        zip(u, v, dst, |x, y| x * y)
+    }
+
+    pub unsafe fn div<T: Element>(u: &[T], v: &[T], dst: &mut [T]) {
+        zip(u, v, dst, |x, y| x / y)
+    }
+
+    pub unsafe fn scale<T: Element>(u: &[T], k: T, dst: &mut [T]) {
+        for (d, &x) in dst.iter_mut().zip(u.iter()) {
+            *d = k * x;
+        }
+    }
+
+    pub unsafe fn exp<T: Element>(u: &[T], dst: &mut [T]) {
+        for (d, &x) in dst.iter_mut().zip(u.iter()) {
+            *d = x.exp();
+        }
+    }
+
+    pub unsafe fn sum<T: Element>(xs: &[T]) -> T {
+        let mut p = [T::default(); 8];
+        let mut chunks = xs.chunks_exact(8);
+        for c in &mut chunks {
+            for (s, &x) in p.iter_mut().zip(c.iter()) {
+                *s = *s + x;
+            }
+        }
+        finish_sum(&p, chunks.remainder())
+    }
+
+    pub unsafe fn max<T: Element>(xs: &[T]) -> T {
+        xs.iter().fold(T::NEG_INFINITY, |m, &x| if x > m { x } else { m })
+    }
+}
+
+macro_rules! scalar_kernels {
+    ($t:ty) => {
+        Kernels {
+            add: scalar::add::<$t>,
+            sub: scalar::sub::<$t>,
+            mul: scalar::mul::<$t>,
+            div: scalar::div::<$t>,
+            scale: scalar::scale::<$t>,
+            exp: scalar::exp::<$t>,
+            sum: scalar::sum::<$t>,
+            max: scalar::max::<$t>,
+        }
+    }
+}
+
+static F64_SCALAR: Kernels<f64> = scalar_kernels!(f64);
+static F32_SCALAR: Kernels<f32> = scalar_kernels!(f32);
+
+impl Element for f64 {
+    const NEG_INFINITY: f64 = f64::NEG_INFINITY;
+    const EXP_LO: f64 = -708.;
+    const EXP_HI: f64 = 709.;
+
+    fn exp(self) -> f64 {
+        f64::exp(self)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn kernels(level: Level) -> &'static Kernels<f64> {
+        match level {
+            Level::Scalar => &F64_SCALAR,
+            Level::Sse2 => &x86::F64_SSE2,
+            Level::Avx2 => &x86::F64_AVX2,
+            Level::Avx512 => &x86::F64_AVX512,
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn kernels(_level: Level) -> &'static Kernels<f64> {
+        &F64_SCALAR
+    }
+}
+
+impl Element for f32 {
+    const NEG_INFINITY: f32 = f32::NEG_INFINITY;
+    const EXP_LO: f32 = -87.;
+    const EXP_HI: f32 = 88.;
+
+    fn exp(self) -> f32 {
+        f32::exp(self)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn kernels(level: Level) -> &'static Kernels<f32> {
+        match level {
+            Level::Scalar => &F32_SCALAR,
+            Level::Sse2 => &x86::F32_SSE2,
+            Level::Avx2 => &x86::F32_AVX2,
+            Level::Avx512 => &x86::F32_AVX512,
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn kernels(_level: Level) -> &'static Kernels<f32> {
+        &F32_SCALAR
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{Element, Kernels, finish_sum};
+    use std::arch::x86_64::*;
+
+    // The kernels are written once against this trait and instantiated per
+    // vector type inside `#[target_feature]` functions, which is where the
+    // intrinsics get inlined.
+    pub trait Simd {
+        type Elem: Element + ExpConsts;
+        type V: Copy;
+        const LANES: usize;
+
+        unsafe fn splat(x: Self::Elem) -> Self::V;
+        unsafe fn load(p: *const Self::Elem) -> Self::V;
+        unsafe fn store(p: *mut Self::Elem, v: Self::V);
+        unsafe fn add(a: Self::V, b: Self::V) -> Self::V;
+        unsafe fn sub(a: Self::V, b: Self::V) -> Self::V;
+        unsafe fn mul(a: Self::V, b: Self::V) -> Self::V;
+        unsafe fn div(a: Self::V, b: Self::V) -> Self::V;
+        // b wherever either is NaN
+        unsafe fn max(a: Self::V, b: Self::V) -> Self::V;
+        unsafe fn min(a: Self::V, b: Self::V) -> Self::V;
+        // 2^n for `t` holding the biased exponent n in its low mantissa
+        // bits, see `exp`
+        unsafe fn pow2(t: Self::V) -> Self::V;
+    }
+
+    // Cody-Waite reduction, exp(x) = 2^n * exp(r) with |r| <= ln(2) / 2,
+    // and a Taylor polynomial for exp(r)
+    pub trait ExpConsts: Sized + 'static {
+        const LOG2E: Self;
+        const LN2_HI: Self;
+        const LN2_LO: Self;
+        // 1.5 * 2^(mantissa bits): adding it rounds to an integer that
+        // then sits in the low mantissa bits
+        const ROUND: Self;
+        const BIAS: Self;
+        // highest order first
+        const POLY: &'static [Self];
+    }
+
+    impl ExpConsts for f64 {
+        const LOG2E: f64 = ::std::f64::consts::LOG2_E;
+        const LN2_HI: f64 = 0.6931471803691238;
+        const LN2_LO: f64 = 1.9082149292705877e-10;
+        const ROUND: f64 = 6755399441055744.;
+        const BIAS: f64 = 1023.;
+        const POLY: &'static [f64] = &[
+            1. / 6227020800., 1. / 479001600., 1. / 39916800., 1. / 3628800., 1. / 362880.,
+            1. / 40320., 1. / 5040., 1. / 720., 1. / 120., 1. / 24., 1. / 6., 1. / 2., 1., 1.,
+        ];
+    }
+
+    impl ExpConsts for f32 {
+        const LOG2E: f32 = ::std::f32::consts::LOG2_E;
+        const LN2_HI: f32 = 0.693_359_4;
+        const LN2_LO: f32 = -2.121_944_4e-4;
+        const ROUND: f32 = 12582912.;
+        const BIAS: f32 = 127.;
+        const POLY: &'static [f32] = &[
+            1. / 40320., 1. / 5040., 1. / 720., 1. / 120., 1. / 24., 1. / 6., 1. / 2., 1., 1.,
+        ];
+    }
+
+    macro_rules! simd_impl {
+        ($name:ident, $elem:ty, $v:ty, $lanes:expr, $shift:expr,
+         $set1:ident, $loadu:ident, $storeu:ident, $add:ident, $sub:ident, $mul:ident, $div:ident,
+         $max:ident, $min:ident, $to_int:ident, $from_int:ident, $slli:ident) => {
+            pub struct $name;
+
+            impl Simd for $name {
+                type Elem = $elem;
+                type V = $v;
+                const LANES: usize = $lanes;
+
+                #[inline(always)]
+                unsafe fn splat(x: $elem) -> $v {
+                    $set1(x)
+                }
+
+                #[inline(always)]
+                unsafe fn load(p: *const $elem) -> $v {
+                    $loadu(p)
+                }
+
+                #[inline(always)]
+                unsafe fn store(p: *mut $elem, v: $v) {
+                    $storeu(p, v)
+                }
+
+                #[inline(always)]
+                unsafe fn add(a: $v, b: $v) -> $v {
+                    $add(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn sub(a: $v, b: $v) -> $v {
+                    $sub(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn mul(a: $v, b: $v) -> $v {
+                    $mul(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn div(a: $v, b: $v) -> $v {
+                    $div(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn max(a: $v, b: $v) -> $v {
+                    $max(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn min(a: $v, b: $v) -> $v {
+                    $min(a, b)
+                }
+
+                #[inline(always)]
+                unsafe fn pow2(t: $v) -> $v {
+                    $from_int($slli($to_int(t), $shift))
+                }
+            }
+        }
+    }
+
+    simd_impl!(Sse2F64, f64, __m128d, 2, 52, _mm_set1_pd, _mm_loadu_pd, _mm_storeu_pd,
+               _mm_add_pd, _mm_sub_pd, _mm_mul_pd, _mm_div_pd, _mm_max_pd, _mm_min_pd,
+               _mm_castpd_si128, _mm_castsi128_pd, _mm_slli_epi64);
+    simd_impl!(Sse2F32, f32, __m128, 4, 23, _mm_set1_ps, _mm_loadu_ps, _mm_storeu_ps,
+               _mm_add_ps, _mm_sub_ps, _mm_mul_ps, _mm_div_ps, _mm_max_ps, _mm_min_ps,
+               _mm_castps_si128, _mm_castsi128_ps, _mm_slli_epi32);
+    simd_impl!(Avx2F64, f64, __m256d, 4, 52, _mm256_set1_pd, _mm256_loadu_pd, _mm256_storeu_pd,
+               _mm256_add_pd, _mm256_sub_pd, _mm256_mul_pd, _mm256_div_pd, _mm256_max_pd, _mm256_min_pd,
+               _mm256_castpd_si256, _mm256_castsi256_pd, _mm256_slli_epi64);
+    simd_impl!(Avx2F32, f32, __m256, 8, 23, _mm256_set1_ps, _mm256_loadu_ps, _mm256_storeu_ps,
+               _mm256_add_ps, _mm256_sub_ps, _mm256_mul_ps, _mm256_div_ps, _mm256_max_ps, _mm256_min_ps,
+               _mm256_castps_si256, _mm256_castsi256_ps, _mm256_slli_epi32);
+    simd_impl!(Avx512F64, f64, __m512d, 8, 52, _mm512_set1_pd, _mm512_loadu_pd, _mm512_storeu_pd,
+               _mm512_add_pd, _mm512_sub_pd, _mm512_mul_pd, _mm512_div_pd, _mm512_max_pd, _mm512_min_pd,
+               _mm512_castpd_si512, _mm512_castsi512_pd, _mm512_slli_epi64);
+    simd_impl!(Avx512F32, f32, __m512, 16, 23, _mm512_set1_ps, _mm512_loadu_ps, _mm512_storeu_ps,
+               _mm512_add_ps, _mm512_sub_ps, _mm512_mul_ps, _mm512_div_ps, _mm512_max_ps, _mm512_min_ps,
+               _mm512_castps_si512, _mm512_castsi512_ps, _mm512_slli_epi32);
+
+    // the most lanes of any vector type
+    const MAX_LANES: usize = 16;
+
+    #[inline(always)]
+    unsafe fn zip<S: Simd, F: Fn(S::V, S::V) -> S::V>(u: &[S::Elem], v: &[S::Elem], dst: &mut [S::Elem], f: F) {
+        let len = dst.len().min(u.len()).min(v.len());
+        let mut i = 0;
+        while i + S::LANES <= len {
+            S::store(dst.as_mut_ptr().add(i), f(S::load(u.as_ptr().add(i)), S::load(v.as_ptr().add(i))));
+            i += S::LANES;
+        }
+        // the tail goes through a zero-padded vector
+        if i < len {
+            let (mut a, mut b, mut out) = ([S::Elem::default(); MAX_LANES], [S::Elem::default(); MAX_LANES],
+                                           [S::Elem::default(); MAX_LANES]);
+            a[..len - i].copy_from_slice(&u[i..len]);
+            b[..len - i].copy_from_slice(&v[i..len]);
+            S::store(out.as_mut_ptr(), f(S::load(a.as_ptr()), S::load(b.as_ptr())));
+            dst[i..len].copy_from_slice(&out[..len - i]);
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn map<S: Simd, F: Fn(S::V) -> S::V>(u: &[S::Elem], dst: &mut [S::Elem], f: F) {
+        let len = dst.len().min(u.len());
+        let mut i = 0;
+        while i + S::LANES <= len {
+            S::store(dst.as_mut_ptr().add(i), f(S::load(u.as_ptr().add(i))));
+            i += S::LANES;
+        }
+        if i < len {
+            let (mut a, mut out) = ([S::Elem::default(); MAX_LANES], [S::Elem::default(); MAX_LANES]);
+            a[..len - i].copy_from_slice(&u[i..len]);
+            S::store(out.as_mut_ptr(), f(S::load(a.as_ptr())));
+            dst[i..len].copy_from_slice(&out[..len - i]);
+        }
+    }
+
+    // Lane j of the accumulators holds running sum j of `unrolled_sum`, the
+    // elements 8k + j, so the vectors must have at most eight lanes.
+    #[inline(always)]
+    unsafe fn sum8<S: Simd>(xs: &[S::Elem]) -> S::Elem {
+        debug_assert!(S::LANES <= 8);
+        let (p, n) = (xs.as_ptr(), 8 / S::LANES);
+        let mut acc = [S::splat(S::Elem::default()); 4];
+        let mut i = 0;
+        while i + 8 <= xs.len() {
+            for (k, a) in acc[..n].iter_mut().enumerate() {
+                *a = S::add(*a, S::load(p.add(i + k * S::LANES)));
+            }
+            i += 8;
+        }
+        let mut sums = [S::Elem::default(); 8];
+        for (k, a) in acc[..n].iter().enumerate() {
+            S::store(sums.as_mut_ptr().add(k * S::LANES), *a);
+        }
+        finish_sum(&sums, &xs[i..])
+    }
+
+    // Folds `xs` into four vector accumulators, to hide the latency,
+    // and returns their lanes once combined; `init` pads the tail.
+    #[inline(always)]
+    unsafe fn fold<S: Simd, F: Fn(S::V, S::V) -> S::V>(xs: &[S::Elem], init: S::Elem, f: F) -> [S::Elem; MAX_LANES] {
+        let p = xs.as_ptr();
+        let mut acc = [S::splat(init); 4];
+        let mut i = 0;
+        while i + 4 * S::LANES <= xs.len() {
+            for (k, a) in acc.iter_mut().enumerate() {
+                *a = f(*a, S::load(p.add(i + k * S::LANES)));
+            }
+            i += 4 * S::LANES;
+        }
+        while i + S::LANES <= xs.len() {
+            acc[0] = f(acc[0], S::load(p.add(i)));
+            i += S::LANES;
+        }
+        if i < xs.len() {
+            let mut a = [init; MAX_LANES];
+            a[..xs.len() - i].copy_from_slice(&xs[i..]);
+            acc[0] = f(acc[0], S::load(a.as_ptr()));
+        }
+        let mut lanes = [init; MAX_LANES];
+        S::store(lanes.as_mut_ptr(), f(f(acc[0], acc[1]), f(acc[2], acc[3])));
+        lanes
+    }
+
+    #[inline(always)]
+    unsafe fn exp_v<S: Simd>(x: S::V) -> S::V {
+        let c = |v: S::Elem| S::splat(v);
+        let x = S::max(S::min(x, c(S::Elem::EXP_HI)), c(S::Elem::EXP_LO));
+        let t = S::add(S::mul(x, c(S::Elem::LOG2E)), c(S::Elem::ROUND));
+        let n = S::sub(t, c(S::Elem::ROUND));
+        let r = S::sub(S::sub(x, S::mul(n, c(S::Elem::LN2_HI))), S::mul(n, c(S::Elem::LN2_LO)));
+
+        let poly = S::Elem::POLY;
+        let mut p = c(poly[0]);
+        for &k in poly[1..].iter() {
+            p = S::add(S::mul(p, r), c(k));
+        }
+        S::mul(p, S::pow2(S::add(t, c(S::Elem::BIAS))))
+    }
+
+    macro_rules! level_kernels {
+        ($m:ident, $feature:tt) => {
+            pub mod $m {
+                use super::{Simd, zip, map, fold, exp_v, sum8};
+                use simd::Element;
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn add<S: Simd>(u: &[S::Elem], v: &[S::Elem], dst: &mut [S::Elem]) {
+                    zip::<S, _>(u, v, dst, |a, b| S::add(a, b))
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn sub<S: Simd>(u: &[S::Elem], v: &[S::Elem], dst: &mut [S::Elem]) {
+                    zip::<S, _>(u, v, dst, |a, b| S::sub(a, b))
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn mul<S: Simd>(u: &[S::Elem], v: &[S::Elem], dst: &mut [S::Elem]) {
+                    zip::<S, _>(u, v, dst, |a, b| S::mul(a, b))
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn div<S: Simd>(u: &[S::Elem], v: &[S::Elem], dst: &mut [S::Elem]) {
+                    zip::<S, _>(u, v, dst, |a, b| S::div(a, b))
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn scale<S: Simd>(u: &[S::Elem], k: S::Elem, dst: &mut [S::Elem]) {
+                    let k = S::splat(k);
+                    map::<S, _>(u, dst, |a| S::mul(k, a))
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn exp<S: Simd>(u: &[S::Elem], dst: &mut [S::Elem]) {
+                    map::<S, _>(u, dst, |a| exp_v::<S>(a));
+                    // out of range inputs and NaNs were clamped
+                    for (d, &x) in dst.iter_mut().zip(u.iter()) {
+                        if !(x >= S::Elem::EXP_LO && x <= S::Elem::EXP_HI) {
+                            *d = x.exp();
+                        }
+                    }
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn sum<S: Simd>(xs: &[S::Elem]) -> S::Elem {
+                    sum8::<S>(xs)
+                }
+
+                #[target_feature(enable = $feature)]
+                pub unsafe fn max<S: Simd>(xs: &[S::Elem]) -> S::Elem {
+                    // the accumulator goes second so NaNs in `xs` are skipped
+                    let lanes = fold::<S, _>(xs, S::Elem::NEG_INFINITY, |acc, x| S::max(x, acc));
+                    lanes[..S::LANES].iter().fold(S::Elem::NEG_INFINITY, |m, &x| if x > m { x } else { m })
+                }
+            }
+        }
+    }
+
+    level_kernels!(sse2, "sse2");
+    level_kernels!(avx2, "avx2");
+    level_kernels!(avx512, "avx512f");
+
+    macro_rules! kernels {
+        ($m:ident, $s:ty) => {
+            Kernels {
+                add: $m::add::<$s>,
+                sub: $m::sub::<$s>,
+                mul: $m::mul::<$s>,
+                div: $m::div::<$s>,
+                scale: $m::scale::<$s>,
+                exp: $m::exp::<$s>,
+                sum: $m::sum::<$s>,
+                max: $m::max::<$s>,
+            }
+        }
+    }
+
+    pub static F64_SSE2: Kernels<f64> = kernels!(sse2, Sse2F64);
+    pub static F32_SSE2: Kernels<f32> = kernels!(sse2, Sse2F32);
+    pub static F64_AVX2: Kernels<f64> = kernels!(avx2, Avx2F64);
+    pub static F32_AVX2: Kernels<f32> = kernels!(avx2, Avx2F32);
+    pub static F64_AVX512: Kernels<f64> = kernels!(avx512, Avx512F64);
+    // sixteen f32 lanes are too many for `sum8`
+    pub static F32_AVX512: Kernels<f32> = Kernels { sum: avx2::sum::<Avx2F32>, ..kernels!(avx512, Avx512F32) };
+}
@@ -0,0 +1,127 @@
+use Matrix2d;
+use utils::Layout;
+
+// Compressed sparse row matrix: the column indices and values of row `r`
+// are `indices[indptr[r]..indptr[r + 1]]` and likewise in `data`, sorted by
+// column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsrMatrix {
+    n_rows: usize,
+    n_cols: usize,
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<f64>,
+}
+
+impl CsrMatrix {
+    // Builds from (row, col, value) triplets in any order, summing duplicates.
+    // Returns None if an index is out of bounds.
+    pub fn from_triplets(n_rows: usize, n_cols: usize, triplets: &[(usize, usize, f64)]) -> Option<CsrMatrix> {
+        if triplets.iter().any(|&(r, c, _)| r >= n_rows || c >= n_cols) {
+            return None;
+        }
+
+        let mut sorted = triplets.to_vec();
+        sorted.sort_by_key(|&(r, c, _)| (r, c));
+
+        let mut indptr = vec![0; n_rows + 1];
+        let mut indices: Vec<usize> = Vec::with_capacity(sorted.len());
+        let mut data: Vec<f64> = Vec::with_capacity(sorted.len());
+        let mut last = None;
+        for &(r, c, v) in sorted.iter() {
+            if last == Some((r, c)) {
+                *data.last_mut().unwrap() += v;
+                continue;
+            }
+            indices.push(c);
+            data.push(v);
+            indptr[r + 1] += 1;
+            last = Some((r, c));
+        }
+        for r in 0..n_rows {
+            indptr[r + 1] += indptr[r];
+        }
+
+        Some(CsrMatrix { n_rows, n_cols, indptr, indices, data })
+    }
+
+    // keeps the non-zero elements of `m`
+    pub fn from_dense(m: &Matrix2d) -> CsrMatrix {
+        let triplets = (0..m.get_rows())
+            .flat_map(|r| {
+                m.get_row(r).unwrap().into_iter()
+                    .enumerate()
+                    .filter(|&(_, v)| v != 0.)
+                    .map(move |(c, v)| (r, c, v))
+            })
+            .collect::<Vec<(usize, usize, f64)>>();
+        CsrMatrix::from_triplets(m.get_rows(), m.get_cols(), &triplets).unwrap()
+    }
+
+    pub fn to_dense(&self) -> Matrix2d {
+        let mut vec = vec![0.; self.n_rows * self.n_cols];
+        for (r, c, v) in self.triplets() {
+            vec[r * self.n_cols + c] = v;
+        }
+        Matrix2d::from_vec_with_layout(vec, self.n_rows, self.n_cols, Layout::RowMajor).unwrap()
+    }
+
+    // stored elements in row-major order
+    pub fn triplets(&self) -> Vec<(usize, usize, f64)> {
+        (0..self.n_rows)
+            .flat_map(|r| {
+                (self.indptr[r]..self.indptr[r + 1]).map(move |i| (r, self.indices[i], self.data[i]))
+            })
+            .collect()
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        if row >= self.n_rows || col >= self.n_cols {
+            return None;
+        }
+        let (start, end) = (self.indptr[row], self.indptr[row + 1]);
+        Some(match self.indices[start..end].binary_search(&col) {
+            Ok(i) => self.data[start + i],
+            Err(_) => 0.,
+        })
+    }
+
+    pub fn get_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn get_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    // number of stored elements, explicit zeros included
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn transpose(&self) -> CsrMatrix {
+        let triplets = self.triplets().into_iter().map(|(r, c, v)| (c, r, v)).collect::<Vec<_>>();
+        CsrMatrix::from_triplets(self.n_cols, self.n_rows, &triplets).unwrap()
+    }
+
+    // sparse times dense
+    pub fn dot(&self, m: &Matrix2d) -> Option<Matrix2d> {
+        if self.n_cols != m.get_rows() {
+            return None;
+        }
+        let k = m.get_cols();
+        let rows = (0..m.get_rows()).map(|r| m.get_row(r).unwrap()).collect::<Vec<Vec<f64>>>();
+
+        let mut out = vec![0.; self.n_rows * k];
+        for r in 0..self.n_rows {
+            let acc = &mut out[r * k..(r + 1) * k];
+            for i in self.indptr[r]..self.indptr[r + 1] {
+                let v = self.data[i];
+                for (a, b) in acc.iter_mut().zip(rows[self.indices[i]].iter()) {
+                    *a += v * b;
+                }
+            }
+        }
+        Matrix2d::from_vec_with_layout(out, self.n_rows, k, Layout::RowMajor)
+    }
+}
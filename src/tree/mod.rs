@@ -0,0 +1,377 @@
+use Matrix2d;
+use utils::flatten;
+
+use rand::distributions::{IndependentSample, Range};
+use rand::{SeedableRng, StdRng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Criterion {
+    Gini,
+    Entropy,
+    Mse,
+}
+
+// sorted distinct labels of a single-column `y` plus each row's class
+// index, None if a label isn't finite
+pub(crate) fn encode_labels(y: &Matrix2d) -> Option<(Vec<f64>, Vec<f64>)> {
+    let labels = y.get_col(0).unwrap();
+    if !labels.iter().all(|l| l.is_finite()) {
+        return None;
+    }
+    let mut classes = labels.clone();
+    classes.sort_by(f64::total_cmp);
+    classes.dedup();
+    let encoded = labels.iter()
+        .map(|l| classes.iter().position(|c| c == l).unwrap() as f64)
+        .collect();
+    Some((classes, encoded))
+}
+
+pub(crate) fn argmax_labels(proba: &Matrix2d, classes: &[f64]) -> Matrix2d {
+    let labels = (0..proba.get_rows())
+        .map(|r| {
+            let row = proba.get_row(r).unwrap();
+            let best = (0..row.len()).fold(0, |b, i| if row[i] > row[b] { i } else { b });
+            classes[best]
+        })
+        .collect();
+    Matrix2d::from_vec_owned(labels, proba.get_rows(), 1)
+}
+
+// Training data shared by the tree builders: row-major features and one
+// target per row, either a class index (n_classes > 0) or a regression value.
+pub(crate) struct Dataset<'a> {
+    pub x: &'a [f64],
+    pub n_features: usize,
+    pub y: &'a [f64],
+    pub n_classes: usize,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct TreeParams {
+    pub criterion: Criterion,
+    pub max_depth: Option<usize>,
+    pub min_samples_split: usize,
+    pub min_samples_leaf: usize,
+    pub max_features: Option<usize>,
+}
+
+enum Node {
+    // class probabilities, or the mean target
+    Leaf(Vec<f64>),
+    Split { feature: usize, threshold: f64, left: usize, right: usize },
+}
+
+pub(crate) struct Tree {
+    nodes: Vec<Node>,
+    n_features: usize,
+}
+
+// running sufficient statistics for one side of a split
+#[derive(Clone)]
+struct Stats {
+    n: f64,
+    counts: Vec<f64>,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl Stats {
+    fn new(n_classes: usize) -> Stats {
+        Stats { n: 0., counts: vec![0.; n_classes], sum: 0., sum_sq: 0. }
+    }
+
+    fn add(&mut self, y: f64, classification: bool, sign: f64) {
+        self.n += sign;
+        if classification {
+            self.counts[y as usize] += sign;
+        } else {
+            self.sum += sign * y;
+            self.sum_sq += sign * y * y;
+        }
+    }
+
+    fn impurity(&self, criterion: Criterion) -> f64 {
+        if self.n == 0. {
+            return 0.;
+        }
+        match criterion {
+            Criterion::Gini => 1. - self.counts.iter().map(|c| (c / self.n) * (c / self.n)).sum::<f64>(),
+            Criterion::Entropy => -self.counts.iter()
+                .filter(|&&c| c > 0.)
+                .map(|c| (c / self.n) * (c / self.n).log2())
+                .sum::<f64>(),
+            Criterion::Mse => (self.sum_sq / self.n - (self.sum / self.n).powi(2)).max(0.),
+        }
+    }
+
+    fn value(&self, classification: bool) -> Vec<f64> {
+        if classification {
+            self.counts.iter().map(|c| c / self.n).collect()
+        } else {
+            vec![self.sum / self.n]
+        }
+    }
+}
+
+impl Tree {
+    // `samples` may repeat rows, which is how bootstrap samples are passed in
+    pub(crate) fn fit(data: &Dataset, samples: &mut [usize], params: &TreeParams, rng: &mut StdRng) -> Tree {
+        let mut tree = Tree { nodes: Vec::new(), n_features: data.n_features };
+        tree.build(data, samples, params, 0, rng);
+        tree
+    }
+
+    fn build(&mut self, data: &Dataset, samples: &mut [usize], params: &TreeParams,
+             depth: usize, rng: &mut StdRng) -> usize {
+        let classification = data.n_classes > 0;
+        let mut total = Stats::new(data.n_classes);
+        for &s in samples.iter() {
+            total.add(data.y[s], classification, 1.);
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node::Leaf(total.value(classification)));
+
+        if params.max_depth.is_some_and(|d| depth >= d) ||
+           samples.len() < params.min_samples_split.max(2) ||
+           total.impurity(params.criterion) <= 0. {
+            return id;
+        }
+
+        let (feature, threshold) = match best_split(data, samples, params, &total, rng) {
+            Some(split) => split,
+            None => return id,
+        };
+
+        // partition in place: rows going left first
+        let mut mid = 0;
+        for i in 0..samples.len() {
+            if data.x[samples[i] * data.n_features + feature] <= threshold {
+                samples.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        let (left_samples, right_samples) = samples.split_at_mut(mid);
+        let left = self.build(data, left_samples, params, depth + 1, rng);
+        let right = self.build(data, right_samples, params, depth + 1, rng);
+        self.nodes[id] = Node::Split { feature, threshold, left, right };
+        id
+    }
+
+    pub(crate) fn predict_row(&self, row: &[f64]) -> &[f64] {
+        let mut id = 0;
+        loop {
+            match self.nodes[id] {
+                Node::Leaf(ref value) => return value,
+                Node::Split { feature, threshold, left, right } => {
+                    id = if row[feature] <= threshold { left } else { right };
+                }
+            }
+        }
+    }
+
+    pub(crate) fn predict(&self, x: &Matrix2d, n_outputs: usize) -> Option<Matrix2d> {
+        if x.get_cols() != self.n_features {
+            return None;
+        }
+        let vec = (0..x.get_rows())
+            .flat_map(|r| self.predict_row(&x.get_row(r).unwrap()).to_vec())
+            .collect();
+        Some(Matrix2d::from_vec_owned(vec, x.get_rows(), n_outputs))
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        fn walk(nodes: &[Node], id: usize) -> usize {
+            match nodes[id] {
+                Node::Leaf(_) => 0,
+                Node::Split { left, right, .. } => 1 + walk(nodes, left).max(walk(nodes, right)),
+            }
+        }
+        walk(&self.nodes, 0)
+    }
+
+    pub(crate) fn n_leaves(&self) -> usize {
+        self.nodes.iter().filter(|n| matches!(n, Node::Leaf(_))).count()
+    }
+}
+
+fn candidate_features(n_features: usize, max_features: Option<usize>, rng: &mut StdRng) -> Vec<usize> {
+    let mut features = (0..n_features).collect::<Vec<usize>>();
+    let k = max_features.map_or(n_features, |m| m.max(1).min(n_features));
+    if k < n_features {
+        for i in 0..k {
+            let j = Range::new(i, n_features).ind_sample(rng);
+            features.swap(i, j);
+        }
+        features.truncate(k);
+    }
+    features
+}
+
+// lowest weighted child impurity over the candidate features; a split that
+// doesn't improve on the parent is still taken (XOR needs one to get going)
+fn best_split(data: &Dataset, samples: &[usize], params: &TreeParams, total: &Stats,
+              rng: &mut StdRng) -> Option<(usize, f64)> {
+    let classification = data.n_classes > 0;
+    let n = samples.len();
+    let min_leaf = params.min_samples_leaf.max(1);
+    let mut best: Option<(f64, usize, f64)> = None;
+    let mut order = samples.to_vec();
+
+    for feature in candidate_features(data.n_features, params.max_features, rng) {
+        let value = |s: usize| data.x[s * data.n_features + feature];
+        order.sort_by(|&a, &b| value(a).total_cmp(&value(b)));
+
+        let mut left = Stats::new(data.n_classes);
+        let mut right = total.clone();
+        for i in 0..n - 1 {
+            let y = data.y[order[i]];
+            left.add(y, classification, 1.);
+            right.add(y, classification, -1.);
+
+            let (here, next) = (value(order[i]), value(order[i + 1]));
+            // a NaN threshold would send every row the same way
+            let threshold = here + (next - here) / 2.;
+            if here == next || threshold.is_nan() || i + 1 < min_leaf || n - i - 1 < min_leaf {
+                continue;
+            }
+
+            let impurity = (left.n * left.impurity(params.criterion) +
+                            right.n * right.impurity(params.criterion)) / n as f64;
+            if best.is_none_or(|(b, _, _)| impurity < b) {
+                best = Some((impurity, feature, threshold));
+            }
+        }
+    }
+    best.map(|(_, feature, threshold)| (feature, threshold))
+}
+
+pub struct DecisionTreeClassifier {
+    params: TreeParams,
+    classes: Vec<f64>,
+    tree: Option<Tree>,
+}
+
+impl DecisionTreeClassifier {
+    pub fn new(criterion: Criterion) -> DecisionTreeClassifier {
+        DecisionTreeClassifier::with_params(criterion, None, 2, 1)
+    }
+
+    // `max_depth` of None grows until leaves are pure or too small to split
+    pub fn with_params(criterion: Criterion, max_depth: Option<usize>, min_samples_split: usize,
+                       min_samples_leaf: usize) -> DecisionTreeClassifier {
+        DecisionTreeClassifier {
+            params: TreeParams {
+                criterion,
+                max_depth,
+                min_samples_split,
+                min_samples_leaf,
+                max_features: None,
+            },
+            classes: Vec::new(),
+            tree: None,
+        }
+    }
+
+    // `y` is a single column of class labels; None for shape mismatches,
+    // non-finite labels or a criterion meant for regression
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() == 0 ||
+           self.params.criterion == Criterion::Mse {
+            return None;
+        }
+
+        let (classes, encoded) = encode_labels(y)?;
+        let features = flatten(x);
+        let data = Dataset { x: &features, n_features: x.get_cols(), y: &encoded, n_classes: classes.len() };
+        let mut samples = (0..x.get_rows()).collect::<Vec<usize>>();
+        // only drawn from when max_features subsamples the columns
+        let mut rng: StdRng = StdRng::from_seed(&[0][..]);
+
+        self.tree = Some(Tree::fit(&data, &mut samples, &self.params, &mut rng));
+        self.classes = classes;
+        Some(())
+    }
+
+    // n x n_classes leaf class frequencies, columns ordered like `get_classes`
+    pub fn predict_proba(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        self.tree.as_ref()?.predict(x, self.classes.len())
+    }
+
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        Some(argmax_labels(&self.predict_proba(x)?, &self.classes))
+    }
+
+    pub fn get_classes(&self) -> &[f64] {
+        &self.classes
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.tree.as_ref().map_or(0, |t| t.depth())
+    }
+
+    pub fn get_n_leaves(&self) -> usize {
+        self.tree.as_ref().map_or(0, |t| t.n_leaves())
+    }
+}
+
+pub struct DecisionTreeRegressor {
+    params: TreeParams,
+    tree: Option<Tree>,
+}
+
+impl DecisionTreeRegressor {
+    pub fn new() -> DecisionTreeRegressor {
+        DecisionTreeRegressor::with_params(None, 2, 1)
+    }
+
+    pub fn with_params(max_depth: Option<usize>, min_samples_split: usize, min_samples_leaf: usize) -> DecisionTreeRegressor {
+        DecisionTreeRegressor {
+            params: TreeParams {
+                criterion: Criterion::Mse,
+                max_depth,
+                min_samples_split,
+                min_samples_leaf,
+                max_features: None,
+            },
+            tree: None,
+        }
+    }
+
+    // `y` is a single target column
+    pub fn fit(&mut self, x: &Matrix2d, y: &Matrix2d) -> Option<()> {
+        if x.get_rows() != y.get_rows() || y.get_cols() != 1 || x.get_rows() == 0 {
+            return None;
+        }
+
+        let features = flatten(x);
+        let targets = y.get_col(0).unwrap();
+        let data = Dataset { x: &features, n_features: x.get_cols(), y: &targets, n_classes: 0 };
+        let mut samples = (0..x.get_rows()).collect::<Vec<usize>>();
+        // only drawn from when max_features subsamples the columns
+        let mut rng: StdRng = StdRng::from_seed(&[0][..]);
+
+        self.tree = Some(Tree::fit(&data, &mut samples, &self.params, &mut rng));
+        Some(())
+    }
+
+    pub fn predict(&self, x: &Matrix2d) -> Option<Matrix2d> {
+        self.tree.as_ref()?.predict(x, 1)
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.tree.as_ref().map_or(0, |t| t.depth())
+    }
+
+    pub fn get_n_leaves(&self) -> usize {
+        self.tree.as_ref().map_or(0, |t| t.n_leaves())
+    }
+}
+
+impl Default for DecisionTreeRegressor {
+    fn default() -> DecisionTreeRegressor {
+        DecisionTreeRegressor::new()
+    }
+}
@@ -1,5 +1,7 @@
 use Matrix2d;
+use simd;
 use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use rayon;
 use num_cpus;
@@ -10,38 +12,54 @@ pub fn sum_vec(vec: &[f64]) -> f64 {
 }
 
 pub fn frobenius_norm(m: &Matrix2d) -> f64 {
-    let mut mc = vec_bin_op(m.get_matrix(), m.get_matrix(), |x, y| x * y);
-    unrolled_sum(&mut mc).sqrt()
+    m.par_norm(Norm::L2)
 }
 
-// from rulinalg, originally from bluss / ndarray
-pub fn unrolled_sum(mut xs: &[f64]) -> f64
-{
-    // eightfold unrolled so that floating point can be vectorized
-    // (even with strict floating point accuracy semantics)
-    let mut sum = 0.;
-    let (mut p0, mut p1, mut p2, mut p3, mut p4, mut p5, mut p6, mut p7) =
-        (0., 0., 0., 0., 0., 0., 0., 0.);
-    while xs.len() >= 8 {
-        p0 = p0 + xs[0].clone();
-        p1 = p1 + xs[1].clone();
-        p2 = p2 + xs[2].clone();
-        p3 = p3 + xs[3].clone();
-        p4 = p4 + xs[4].clone();
-        p5 = p5 + xs[5].clone();
-        p6 = p6 + xs[6].clone();
-        p7 = p7 + xs[7].clone();
+// the elements row by row
+pub(crate) fn flatten(m: &Matrix2d) -> Vec<f64> {
+    (0..m.get_rows())
+        .flat_map(|row| m.get_row(row).unwrap())
+        .collect::<Vec<f64>>()
+}
 
-        xs = &xs[8..];
+pub(crate) fn same_shape(a: &Matrix2d, b: &Matrix2d) -> bool {
+    a.get_rows() == b.get_rows() && a.get_cols() == b.get_cols()
+}
+
+// 1 x n_cols of the column sums
+pub(crate) fn sum_rows(m: &Matrix2d) -> Matrix2d {
+    let vec = (0..m.get_cols())
+        .map(|col| m.get_col(col).unwrap().iter().sum())
+        .collect::<Vec<f64>>();
+    Matrix2d::from_vec_owned(vec, 1, m.get_cols())
+}
+
+// the proximal step of the L1 penalty: shrinks `x` towards 0 by `lambda`
+pub(crate) fn soft_threshold(x: f64, lambda: f64) -> f64 {
+    if x > lambda {
+        x - lambda
+    } else if x < -lambda {
+        x + lambda
+    } else {
+        0.
     }
-    sum = sum.clone() + (p0 + p4);
-    sum = sum.clone() + (p1 + p5);
-    sum = sum.clone() + (p2 + p6);
-    sum = sum.clone() + (p3 + p7);
-    for elt in xs {
-        sum = sum.clone() + elt.clone();
+}
+
+// written so neither branch can overflow `exp`
+pub(crate) fn sigmoid(x: f64) -> f64 {
+    if x >= 0. {
+        1. / (1. + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1. + e)
     }
-    sum
+}
+
+// from rulinalg, originally from bluss / ndarray: eight running sums, one
+// per index mod 8, which `simd::sum` keeps in vector lanes
+pub fn unrolled_sum(xs: &[f64]) -> f64
+{
+    simd::sum(xs)
 }
 
 // from rulinalg
@@ -71,7 +89,7 @@ pub fn vec_bin_op<F>(u: &[f64], v: &[f64], f: F) -> Vec<f64>
 }
 
 pub fn vec_bin_op_mut<F>(u: &[f32], v: &[f32], len: usize, dst: &mut [f32], f: &F) -> ()
-where F: Fn(f32, f32) -> f32 + Send + Sync + 'static
+where F: Fn(f32, f32) -> f32 + Sync
 {
     let mut x_iter = u.iter();
     let mut y_iter = v.iter();
@@ -94,7 +112,7 @@ pub fn get_chunk_size<T>(u: &[T], v: &[T]) -> usize {
 
 
 pub fn vec_bin_op_split<F>(u: &[f32], v: &[f32], dst: &mut [f32], chunk_size: &usize, f: &F) -> ()
-    where F: Fn(f32, f32) -> f32 + Send + Sync + 'static
+    where F: Fn(f32, f32) -> f32 + Sync
 {
     // debug_assert!(u.len() == v.len());
     let len = u.len();
@@ -115,7 +133,7 @@ pub fn vec_bin_op_split<F>(u: &[f32], v: &[f32], dst: &mut [f32], chunk_size: &u
 }
 
 pub fn vec_bin_op_threaded<F>(u: &[f32], v: &[f32], chunk_size: &usize, f: &F) -> Vec<f32>
-    where F: Fn(f32, f32) -> f32 + Send + Sync + 'static
+    where F: Fn(f32, f32) -> f32 + Sync
 {
     let len = u.len();
     debug_assert!(len == v.len());
@@ -132,7 +150,7 @@ pub fn vec_bin_op_threaded<F>(u: &[f32], v: &[f32], chunk_size: &usize, f: &F) -
 
 
 pub fn vec_fn_op_mut<F>(u: &[f64], dst: &mut [f64], f: &F) -> ()
-where F: Fn(f64) -> f64 + Send + Sync + 'static
+where F: Fn(f64) -> f64 + Sync
 {
     let mut x_iter = u.iter();
 
@@ -143,7 +161,7 @@ where F: Fn(f64) -> f64 + Send + Sync + 'static
 
 
 pub fn vec_fn_op_split<F>(u: &[f64], dst: &mut [f64], chunk_size: &usize, f: &F) -> ()
-    where F: Fn(f64) -> f64 + Send + Sync + 'static
+    where F: Fn(f64) -> f64 + Sync
 {
     // debug_assert!(u.len() == v.len());
     let len = u.len();
@@ -163,7 +181,7 @@ pub fn vec_fn_op_split<F>(u: &[f64], dst: &mut [f64], chunk_size: &usize, f: &F)
 }
 
 pub fn vec_fn_op_threaded<F>(u: &[f64], chunk_size: &usize, f: &F) -> Vec<f64>
-    where F: Fn(f64) -> f64 + Send + Sync + 'static
+    where F: Fn(f64) -> f64 + Sync
 {
     let len = u.len();
 
@@ -176,3 +194,196 @@ pub fn vec_fn_op_threaded<F>(u: &[f64], chunk_size: &usize, f: &F) -> Vec<f64>
 
     out_vec
 }
+
+// 0 derives the chunk size from the input length and the number of cpus
+static PAR_CHUNK_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+// below this many elements a chunk isn't worth sending to another thread
+const MIN_PAR_CHUNK: usize = 4096;
+
+// Sets the chunk size used by the `par_*` methods of `Matrix2d`, or goes
+// back to deriving it from `num_cpus` with None.
+pub fn set_par_chunk_size(chunk_size: Option<usize>) {
+    PAR_CHUNK_SIZE.store(chunk_size.map_or(0, |c| c.max(1)), Ordering::Relaxed);
+}
+
+pub fn par_chunk_size(len: usize) -> usize {
+    match PAR_CHUNK_SIZE.load(Ordering::Relaxed) {
+        0 => cmp::max(len / num_cpus::get(), MIN_PAR_CHUNK),
+        chunk_size => chunk_size,
+    }
+}
+
+// multiply-adds (m * k * n) from which `Matrix2d::dot` goes parallel, about
+// a 256 x 256 product; 0 means the default
+static PAR_GEMM_THRESHOLD: AtomicUsize = AtomicUsize::new(0);
+
+const DEFAULT_PAR_GEMM_THRESHOLD: usize = 1 << 24;
+
+// Sets the size from which `Matrix2d::dot` runs on the rayon pool, or goes
+// back to the default with None.
+pub fn set_par_gemm_threshold(threshold: Option<usize>) {
+    PAR_GEMM_THRESHOLD.store(threshold.map_or(0, |t| t.max(1)), Ordering::Relaxed);
+}
+
+pub fn par_gemm_threshold() -> usize {
+    match PAR_GEMM_THRESHOLD.load(Ordering::Relaxed) {
+        0 => DEFAULT_PAR_GEMM_THRESHOLD,
+        threshold => threshold,
+    }
+}
+
+// `vec_bin_op` for f64 into `dst`, split across the rayon pool in chunks of
+// at most `chunk_size`; `f` only has to be Sync, so it can borrow locals
+pub fn vec_bin_op_par<F>(u: &[f64], v: &[f64], dst: &mut [f64], chunk_size: usize, f: &F)
+    where F: Fn(f64, f64) -> f64 + Sync
+{
+    debug_assert!(u.len() == v.len() && u.len() == dst.len());
+    let len = dst.len();
+
+    if len <= cmp::max(chunk_size, 1) {
+        for ((d, &x), &y) in dst.iter_mut().zip(u.iter()).zip(v.iter()) {
+            *d = f(x, y);
+        }
+        return;
+    }
+
+    let mid_point = len / 2;
+    let (x_left, x_right) = u.split_at(mid_point);
+    let (y_left, y_right) = v.split_at(mid_point);
+    let (dst_left, dst_right) = dst.split_at_mut(mid_point);
+
+    rayon::join(|| vec_bin_op_par(x_left, y_left, dst_left, chunk_size, f),
+                || vec_bin_op_par(x_right, y_right, dst_right, chunk_size, f));
+}
+
+// the unary counterpart of `vec_bin_op_par`
+pub fn vec_fn_op_par<F>(u: &[f64], dst: &mut [f64], chunk_size: usize, f: &F)
+    where F: Fn(f64) -> f64 + Sync
+{
+    debug_assert!(u.len() == dst.len());
+    let len = dst.len();
+
+    if len <= cmp::max(chunk_size, 1) {
+        for (d, &x) in dst.iter_mut().zip(u.iter()) {
+            *d = f(x);
+        }
+        return;
+    }
+
+    let mid_point = len / 2;
+    let (x_left, x_right) = u.split_at(mid_point);
+    let (dst_left, dst_right) = dst.split_at_mut(mid_point);
+
+    rayon::join(|| vec_fn_op_par(x_left, dst_left, chunk_size, f),
+                || vec_fn_op_par(x_right, dst_right, chunk_size, f));
+}
+
+// Entrywise norms, treating a matrix as one long vector; L2 of a matrix is
+// its Frobenius norm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Norm {
+    L1,
+    L2,
+    Max,
+}
+
+// Order of the elements in a matrix's buffer: `RowMajor` is C order
+// (rs = n_cols, cs = 1), `ColMajor` Fortran order (rs = 1, cs = n_rows).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    RowMajor,
+    ColMajor,
+}
+
+// Which lines an axis reduction runs along: `Cols` reduces every column to
+// give a 1 x n_cols matrix, `Rows` every row to give n_rows x 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    Rows,
+    Cols,
+}
+
+// chunk size of the `par_*` reductions of `Matrix2d`; 0 means the default.
+// Kept apart from `PAR_CHUNK_SIZE` so tuning the elementwise methods never
+// changes how a reduction rounds.
+static REDUCE_CHUNK_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+const DEFAULT_REDUCE_CHUNK_SIZE: usize = 4096;
+
+// Sets the chunk size the reductions split on, or goes back to the default
+// with None.
+pub fn set_reduce_chunk_size(chunk_size: Option<usize>) {
+    REDUCE_CHUNK_SIZE.store(chunk_size.map_or(0, |c| c.max(1)), Ordering::Relaxed);
+}
+
+// Reductions split on a fixed size rather than `num_cpus`, so the
+// reduction tree, and with it the rounding, is the same on every machine.
+pub fn reduce_chunk_size() -> usize {
+    match REDUCE_CHUNK_SIZE.load(Ordering::Relaxed) {
+        0 => DEFAULT_REDUCE_CHUNK_SIZE,
+        chunk_size => chunk_size,
+    }
+}
+
+// Reduces the index range `start..end`: ranges up to `chunk_size` go to
+// `leaf`, longer ones are halved and the halves, computed on the rayon pool,
+// merged with `combine`. The tree only depends on the range and chunk size.
+pub fn par_fold_range<L, C>(start: usize, end: usize, chunk_size: usize, leaf: &L, combine: &C) -> f64
+    where L: Fn(usize, usize) -> f64 + Sync,
+          C: Fn(f64, f64) -> f64 + Sync
+{
+    if end - start <= cmp::max(chunk_size, 1) {
+        return leaf(start, end);
+    }
+    let mid_point = start + (end - start) / 2;
+    let (left, right) = rayon::join(|| par_fold_range(start, mid_point, chunk_size, leaf, combine),
+                                    || par_fold_range(mid_point, end, chunk_size, leaf, combine));
+    combine(left, right)
+}
+
+pub fn par_sum(xs: &[f64], chunk_size: usize) -> f64 {
+    par_fold_range(0, xs.len(), chunk_size, &|a, b| unrolled_sum(&xs[a..b]), &|x, y| x + y)
+}
+
+// dot product of two vectors, without forming their elementwise product
+pub fn par_dot(u: &[f64], v: &[f64], chunk_size: usize) -> f64 {
+    debug_assert_eq!(u.len(), v.len());
+    let len = cmp::min(u.len(), v.len());
+    par_fold_range(0, len, chunk_size, &|a, b| unrolled_dot(&u[a..b], &v[a..b]), &|x, y| x + y)
+}
+
+// `unrolled_sum` of the products of `u` and `v`
+fn unrolled_dot(u: &[f64], v: &[f64]) -> f64 {
+    let mut p = [0.; 8];
+    let (mut xs, mut ys) = (u, v);
+    while xs.len() >= 8 && ys.len() >= 8 {
+        for i in 0..8 {
+            p[i] += xs[i] * ys[i];
+        }
+        xs = &xs[8..];
+        ys = &ys[8..];
+    }
+    let mut sum = (p[0] + p[4]) + (p[1] + p[5]) + (p[2] + p[6]) + (p[3] + p[7]);
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        sum += x * y;
+    }
+    sum
+}
+
+// Fills `dst[i]` with `f(offset + i)` on the rayon pool, `chunk_size`
+// elements per task.
+pub fn par_fill<F>(dst: &mut [f64], offset: usize, chunk_size: usize, f: &F)
+    where F: Fn(usize) -> f64 + Sync
+{
+    if dst.len() <= cmp::max(chunk_size, 1) {
+        for (i, d) in dst.iter_mut().enumerate() {
+            *d = f(offset + i);
+        }
+        return;
+    }
+    let mid_point = dst.len() / 2;
+    let (left, right) = dst.split_at_mut(mid_point);
+    rayon::join(|| par_fill(left, offset, chunk_size, f),
+                || par_fill(right, offset + mid_point, chunk_size, f));
+}
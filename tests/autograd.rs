@@ -0,0 +1,102 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::autograd::*;
+
+// central differences of `f` around each element of `x`
+fn numeric_grad<F>(x: &Matrix2d, f: F) -> Vec<f64>
+    where F: Fn(&Matrix2d) -> f64
+{
+    let h = 1e-6;
+    let base = x.ravel();
+    (0..base.len())
+        .map(|i| {
+            let mut up = base.clone();
+            let mut down = base.clone();
+            up[i] += h;
+            down[i] -= h;
+            (f(&up.reshape(x.get_rows(), x.get_cols()).unwrap()) -
+             f(&down.reshape(x.get_rows(), x.get_cols()).unwrap())) / (2. * h)
+        })
+        .collect::<Vec<f64>>()
+}
+
+fn assert_close(analytic: &Matrix2d, numeric: &[f64]) {
+    for (a, n) in analytic.ravel().iter().zip(numeric.iter()) {
+        assert!((a - n).abs() < 1e-5, "analytic {} vs numeric {}", a, n);
+    }
+}
+
+fn eval<F>(f: F, a: &Matrix2d, b: &Matrix2d) -> f64
+    where F: for<'t> Fn(Var<'t>, Var<'t>) -> Var<'t>
+{
+    let tape = Tape::new();
+    f(tape.var(a), tape.var(b)).value().get_matrix()[0]
+}
+
+fn check<F>(f: F, a: &Matrix2d, b: &Matrix2d)
+    where F: for<'t> Fn(Var<'t>, Var<'t>) -> Var<'t>
+{
+    let tape = Tape::new();
+    let (va, vb) = (tape.var(a), tape.var(b));
+    let grads = f(va, vb).backward();
+
+    assert_close(grads.wrt(&va).unwrap(), &numeric_grad(a, |m| eval(&f, m, b)));
+    assert_close(grads.wrt(&vb).unwrap(), &numeric_grad(b, |m| eval(&f, a, m)));
+}
+
+#[test]
+fn dot_grad() {
+    let a = vec![vec![1., 2., -1.], vec![0.5, -3., 2.]].to_matrix_2d().unwrap();
+    let b = vec![vec![0.3, 1.], vec![-2., 0.7], vec![1.5, -0.2]].to_matrix_2d().unwrap();
+
+    check(|a, b| a.dot(&b).unwrap().sum(), &a, &b);
+}
+
+#[test]
+fn elementwise_grad() {
+    let a = vec![vec![1., 2.], vec![0.5, 3.]].to_matrix_2d().unwrap();
+    let b = vec![vec![0.3, 1.], vec![2., 0.7]].to_matrix_2d().unwrap();
+
+    check(|a, b| a.mult(&b).unwrap().subtract(&a.scale(3.)).unwrap().mean(), &a, &b);
+    check(|a, b| a.addition(&b).unwrap().ln().mult(&a.exp()).unwrap().sum(), &a, &b);
+}
+
+#[test]
+fn transpose_and_activation_grad() {
+    let a = vec![vec![1., -2., 0.4], vec![0.5, -3., 2.]].to_matrix_2d().unwrap();
+    let b = vec![vec![0.3, 1., -0.6], vec![-2., 0.7, 0.1]].to_matrix_2d().unwrap();
+
+    check(|a, b| a.transpose().dot(&b.tanh()).unwrap().sigmoid().sum(), &a, &b);
+    check(|a, b| a.dot(&b.transpose()).unwrap().relu().mean(), &a, &b);
+}
+
+#[test]
+fn reduction_grad() {
+    let x = vec![vec![1., -2., 0.4], vec![0.5, -3., 2.]].to_matrix_2d().unwrap();
+    let bias = vec![vec![0.1, 0.2, 0.3]].to_matrix_2d().unwrap();
+
+    check(|x, b| x.add_row(&b).unwrap().sigmoid().sum_rows().mult(&b).unwrap().sum(), &x, &bias);
+}
+
+#[test]
+fn reused_var_accumulates() {
+    let tape = Tape::new();
+    let x = tape.var(&vec![vec![3.]].to_matrix_2d().unwrap());
+    let y = x.mult(&x).unwrap().addition(&x).unwrap();
+
+    assert!(y.value().get_matrix()[0] == 12.);
+    assert!(y.backward().wrt(&x).unwrap().get_matrix()[0] == 7.);
+}
+
+#[test]
+fn shape_mismatch() {
+    let tape = Tape::new();
+    let a = tape.var(&vec![1., 2.].to_matrix_2d().unwrap());
+    let b = tape.var(&vec![1., 2., 3.].to_matrix_2d().unwrap());
+
+    assert!(a.addition(&b).is_none());
+    assert!(a.dot(&b).is_none());
+    assert!(tape.len() == 2);
+}
@@ -0,0 +1,80 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::cluster::KMeans;
+
+fn blobs() -> Matrix2d {
+    let centers = [(0., 0.), (10., 0.), (0., 10.)];
+    let offsets = [(0.5, -0.2), (-0.4, 0.3), (0.2, 0.6), (-0.3, -0.5), (0., -0.2)];
+    centers.iter()
+        .flat_map(|&(cx, cy)| offsets.iter().map(move |&(dx, dy)| vec![cx + dx, cy + dy]))
+        .collect::<Vec<Vec<f64>>>()
+        .to_matrix_2d()
+        .unwrap()
+}
+
+#[test]
+fn finds_blobs() {
+    let x = blobs();
+    let mut km = KMeans::new(3, &[1, 2, 3]);
+    km.fit(&x).unwrap();
+
+    let labels = km.get_labels();
+    for blob in 0..3 {
+        for i in 0..5 {
+            assert!(labels[blob * 5 + i] == labels[blob * 5]);
+        }
+    }
+    assert!(labels[0] != labels[5] && labels[5] != labels[10] && labels[0] != labels[10]);
+
+    let c = km.get_centroids().unwrap().get_row(labels[5]).unwrap();
+    assert!((c[0] - 10.).abs() < 1e-9 && c[1].abs() < 1e-9);
+
+    let expected = 3. * (0.54 + 0.78);
+    assert!((km.get_inertia() - expected).abs() < 1e-9);
+    assert!(km.get_n_iter() > 0);
+}
+
+#[test]
+fn predict_new_rows() {
+    let x = blobs();
+    let mut km = KMeans::new(3, &[7]);
+    km.fit(&x).unwrap();
+
+    let new = vec![vec![9., 1.], vec![1., 9.], vec![-1., -1.]].to_matrix_2d().unwrap();
+    let labels = km.get_labels();
+    assert!(km.predict(&new).unwrap() == vec![labels[5], labels[10], labels[0]]);
+    assert!(km.predict(&vec![1., 2., 3.].to_matrix_2d().unwrap().transpose()).is_none());
+}
+
+#[test]
+fn seeded_runs_are_reproducible() {
+    let x = Matrix2d::fill_rng(50, 3);
+    let mut a = KMeans::with_params(4, 100, 1e-8, 3, &[42]);
+    let mut b = KMeans::with_params(4, 100, 1e-8, 3, &[42]);
+    a.fit(&x).unwrap();
+    b.fit(&x).unwrap();
+
+    assert!(a.get_labels() == b.get_labels());
+    assert!(a.get_centroids().unwrap() == b.get_centroids().unwrap());
+}
+
+#[test]
+fn more_restarts_never_hurt() {
+    let x = Matrix2d::fill_rng(60, 2);
+    let mut one = KMeans::with_params(5, 100, 1e-8, 1, &[3]);
+    let mut many = KMeans::with_params(5, 100, 1e-8, 10, &[3]);
+    one.fit(&x).unwrap();
+    many.fit(&x).unwrap();
+
+    assert!(many.get_inertia() <= one.get_inertia() + 1e-12);
+}
+
+#[test]
+fn rejects_too_few_rows() {
+    let x = vec![vec![1., 2.], vec![3., 4.]].to_matrix_2d().unwrap();
+    let mut km = KMeans::new(3, &[1]);
+    assert!(km.fit(&x).is_none());
+    assert!(km.predict(&x).is_none());
+}
@@ -0,0 +1,74 @@
+extern crate num_rust;
+extern crate flate2;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::datasets::*;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+
+fn idx_bytes(kind: u8, dims: &[u32], data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0, 0, kind, dims.len() as u8];
+    for d in dims.iter() {
+        out.extend_from_slice(&d.to_be_bytes());
+    }
+    out.extend_from_slice(data);
+    out
+}
+
+#[test]
+fn reads_idx() {
+    // two 2 x 3 "images"
+    let images = idx_bytes(0x08, &[2, 2, 3], &[0, 1, 2, 3, 4, 5, 255, 254, 253, 252, 251, 250]);
+    let x = read_idx(&images[..]).unwrap();
+    assert_eq!((x.get_rows(), x.get_cols()), (2, 6));
+    assert_eq!(x.get_row(1).unwrap(), vec![255., 254., 253., 252., 251., 250.]);
+
+    let data = [-1.5f32, 2.].iter().flat_map(|v| v.to_be_bytes().to_vec()).collect::<Vec<u8>>();
+    let y = read_idx(&idx_bytes(0x0D, &[2], &data)[..]).unwrap();
+    assert_eq!(y, vec![-1.5, 2.].to_matrix_2d().unwrap());
+
+    assert!(read_idx(&idx_bytes(0x08, &[3], &[1, 2])[..]).is_err());
+    assert!(read_idx(&idx_bytes(0x07, &[1], &[1])[..]).is_err());
+    assert!(read_idx(&idx_bytes(0x08, &[u32::MAX, u32::MAX, u32::MAX], &[1])[..]).is_err());
+}
+
+#[test]
+fn loads_gzipped_mnist_files() {
+    let dir = env::temp_dir().join(format!("num_rust_datasets_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let images = dir.join("images-idx3-ubyte.gz");
+    let labels = dir.join("labels-idx1-ubyte");
+
+    let mut gz = GzEncoder::new(File::create(&images).unwrap(), Compression::default());
+    gz.write_all(&idx_bytes(0x08, &[3, 2, 2], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])).unwrap();
+    gz.finish().unwrap();
+    File::create(&labels).unwrap().write_all(&idx_bytes(0x08, &[3], &[7, 2, 1])).unwrap();
+
+    let (x, y) = load_mnist(&images, &labels).unwrap();
+    assert_eq!(x.get_row(2).unwrap(), vec![9., 10., 11., 12.]);
+    assert_eq!(y, vec![7., 2., 1.].to_matrix_2d().unwrap());
+    assert!(load_mnist(&images, &images).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reads_libsvm() {
+    let text = "+1 1:0.5 3:-2 # first\n\n-1 qid:4 2:1.25\n0\n";
+    let (x, y) = read_libsvm(text.as_bytes(), None).unwrap();
+    assert_eq!(x, vec![vec![0.5, 0., -2.], vec![0., 1.25, 0.], vec![0., 0., 0.]].to_matrix_2d().unwrap());
+    assert_eq!(y, vec![1., -1., 0.].to_matrix_2d().unwrap());
+
+    let (x, _) = read_libsvm(text.as_bytes(), Some(5)).unwrap();
+    assert_eq!(x.get_cols(), 5);
+    assert!(read_libsvm(text.as_bytes(), Some(2)).is_err());
+
+    assert!(read_libsvm("1 0:1\n".as_bytes(), None).is_err());
+    assert!(read_libsvm("1 2:x\n".as_bytes(), None).is_err());
+    assert!(read_libsvm("a 2:1\n".as_bytes(), None).is_err());
+    assert!(read_libsvm("1 999999999:1\n".as_bytes(), None).is_err());
+}
@@ -0,0 +1,82 @@
+extern crate num_rust;
+extern crate rand;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::ensemble::*;
+
+use rand::{SeedableRng, StdRng};
+
+// two interleaved half-planes: class 1 when x0 + x1 > 1
+fn dataset(n: usize, offset: usize) -> (Matrix2d, Matrix2d) {
+    let x = (0..n)
+        .map(|i| {
+            let a = ((i + offset) * 37 % 101) as f64 / 100.;
+            let b = ((i + offset) * 53 % 97) as f64 / 96.;
+            vec![a, b, ((i * 13) % 7) as f64]
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let y = x.iter().map(|r| if r[0] + r[1] > 1. { 1. } else { 0. }).collect::<Vec<f64>>();
+    (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+}
+
+fn accuracy(pred: &Matrix2d, y: &Matrix2d) -> f64 {
+    let hits = pred.ravel().iter().zip(y.ravel().iter()).filter(|&(a, b)| a == b).count();
+    hits as f64 / y.get_rows() as f64
+}
+
+#[test]
+fn bootstrap_indices_test() {
+    let mut rng: StdRng = StdRng::from_seed(&[1, 2][..]);
+    let idx = bootstrap_indices(100, &mut rng);
+    assert!(idx.len() == 100 && idx.iter().all(|&i| i < 100));
+
+    // with replacement: some rows repeat and some are left out
+    let mut unique = idx.clone();
+    unique.sort();
+    unique.dedup();
+    assert!(unique.len() < 100);
+}
+
+#[test]
+fn classifier_generalises() {
+    let (x, y) = dataset(200, 0);
+    let (test_x, test_y) = dataset(100, 500);
+
+    let mut forest = RandomForestClassifier::new(25, &[7]);
+    forest.fit(&x, &y).unwrap();
+    assert!(forest.get_n_trees() == 25);
+    assert!(accuracy(&forest.predict(&test_x).unwrap(), &test_y) > 0.9);
+
+    let proba = forest.predict_proba(&test_x).unwrap();
+    assert!((proba.get_row(0).unwrap().iter().sum::<f64>() - 1.).abs() < 1e-12);
+
+    let mut nan_y = y.clone();
+    nan_y.get_matrix_mut()[0] = f64::NAN;
+    assert!(RandomForestClassifier::new(2, &[7]).fit(&x, &nan_y).is_none());
+}
+
+#[test]
+fn seeded_forests_match() {
+    let (x, y) = dataset(100, 0);
+    let mut a = RandomForestClassifier::new(8, &[3]);
+    let mut b = RandomForestClassifier::new(8, &[3]);
+    a.fit(&x, &y).unwrap();
+    b.fit(&x, &y).unwrap();
+
+    assert!(a.predict_proba(&x).unwrap() == b.predict_proba(&x).unwrap());
+}
+
+#[test]
+fn regressor() {
+    let x = (0..100).map(|i| vec![i as f64 / 10.]).collect::<Vec<Vec<f64>>>().to_matrix_2d().unwrap();
+    let y = x.apply_fn(|v| v.sin());
+
+    let mut forest = RandomForestRegressor::new(20, &[11]);
+    forest.fit(&x, &y).unwrap();
+    let pred = forest.predict(&x).unwrap();
+    let err = pred.subtract(&y).unwrap().ravel().iter().map(|e| e * e).sum::<f64>() / 100.;
+    assert!(err < 0.01);
+
+    assert!(RandomForestRegressor::new(2, &[1]).predict(&x).is_none());
+}
@@ -0,0 +1,55 @@
+extern crate num_rust;
+
+use num_rust::expr::Expr;
+use num_rust::ext::traits::ToMatrix2d;
+
+fn sigmoid(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+#[test]
+fn fused_matches_eager() {
+    let a = vec![vec![-1f64, 2f64, 0f64], vec![0f64, 3f64, 6f64]].to_matrix_2d().unwrap();
+    let b = vec![vec![0f64, -4f64, 3f64], vec![9f64, -4f64, -3f64]].to_matrix_2d().unwrap();
+    let c = vec![vec![1f64, 1f64, 1f64], vec![2f64, 2f64, 2f64]].to_matrix_2d().unwrap();
+
+    let eager = a.mult(&b).unwrap().addition(&c).unwrap().apply_fn(sigmoid);
+    let expr = a.lazy().mult(&b).addition(&c).apply_fn(sigmoid);
+    assert!(expr.eval().unwrap() == eager);
+    assert!(expr.par_eval().unwrap() == eager);
+
+    let scaled = a.lazy().subtract(b.lazy().scale(2.)).zip_fn(&c, f64::max);
+    assert!(scaled.eval().unwrap().get_row(1).unwrap() == vec![2f64, 11f64, 12f64]);
+
+    assert!(a.lazy().addition(&a.transpose()).eval().is_none());
+}
+
+#[test]
+fn mixed_layouts_and_eval_into() {
+    let a = vec![vec![1f64, 2f64], vec![3f64, 4f64], vec![5f64, 6f64]].to_matrix_2d().unwrap();
+    let t = vec![vec![10f64, 30f64, 50f64], vec![20f64, 40f64, 60f64]].to_matrix_2d().unwrap().transpose();
+
+    let sum = a.lazy().addition(&t).eval().unwrap();
+    assert!(sum.get_row(2).unwrap() == vec![55f64, 66f64]);
+
+    // written in dst's own layout, here column-major
+    let mut dst = vec![vec![0f64; 3]; 2].to_matrix_2d().unwrap().transpose();
+    a.lazy().addition(&t).eval_into(&mut dst).unwrap();
+    assert!(dst.get_row(2).unwrap() == vec![55f64, 66f64]);
+
+    let mut dst = vec![vec![0f64; 2]; 3].to_matrix_2d().unwrap();
+    t.lazy().apply_fn(|x| x / 10.).par_eval_into(&mut dst).unwrap();
+    assert!(dst.get_row(1).unwrap() == vec![3f64, 4f64]);
+
+    let mut wrong = vec![0f64; 6].to_matrix_2d().unwrap();
+    assert!(a.lazy().eval_into(&mut wrong).is_none());
+}
+
+#[test]
+fn eval_into_shared_buffer() {
+    let a = vec![vec![1f64, 2f64], vec![3f64, 4f64]].to_matrix_2d().unwrap();
+    let mut dst = a.clone();
+    a.lazy().scale(2.).eval_into(&mut dst).unwrap();
+    assert!(dst.get_matrix() == &vec![2f64, 4f64, 6f64, 8f64]);
+    assert!(a.get_matrix() == &vec![1f64, 2f64, 3f64, 4f64]);
+}
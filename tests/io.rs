@@ -0,0 +1,446 @@
+extern crate num_rust;
+
+mod csv {
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::io::csv::*;
+
+    #[test]
+    fn reads_with_header_and_columns() {
+        let text = "a;b;c\n1;2;3\n\n4;\"5\";6\r\n";
+        let options = CsvOptions {
+            delimiter: b';',
+            has_header: true,
+            columns: Some(vec![2, 0]),
+            ..CsvOptions::default()
+        };
+        let m = Matrix2d::from_csv(text.as_bytes(), &options).unwrap();
+        assert_eq!(m, vec![vec![3., 1.], vec![6., 4.]].to_matrix_2d().unwrap());
+
+        let options = CsvOptions { columns: Some(vec![3]), ..options };
+        assert!(Matrix2d::from_csv(text.as_bytes(), &options).is_err());
+    }
+
+    #[test]
+    fn missing_value_policies() {
+        let text = "1,,3\n4,5, \n";
+
+        let err = Matrix2d::from_csv(text.as_bytes(), &CsvOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+
+        let options = CsvOptions { missing: Missing::Fill(-1.), ..CsvOptions::default() };
+        let m = Matrix2d::from_csv(text.as_bytes(), &options).unwrap();
+        assert_eq!(m, vec![vec![1., -1., 3.], vec![4., 5., -1.]].to_matrix_2d().unwrap());
+
+        let options = CsvOptions { missing: Missing::NaN, ..CsvOptions::default() };
+        let m = Matrix2d::from_csv(text.as_bytes(), &options).unwrap();
+        assert!(m.get_row(0).unwrap()[1].is_nan() && m.get_row(1).unwrap()[2].is_nan());
+    }
+
+    #[test]
+    fn rejects_ragged_and_bad_input() {
+        let opts = CsvOptions::default();
+        assert!(Matrix2d::from_csv("1,2\n3\n".as_bytes(), &opts).is_err());
+        assert!(Matrix2d::from_csv("1,x\n".as_bytes(), &opts).is_err());
+        assert!(Matrix2d::from_csv("\n\n".as_bytes(), &opts).is_err());
+    }
+
+    #[test]
+    fn round_trips_and_formats() {
+        let m = vec![vec![0.1, -2.5e-8], vec![1. / 3., 1e20]].to_matrix_2d().unwrap();
+
+        let mut out = Vec::new();
+        m.to_csv(&mut out, &CsvOptions::default()).unwrap();
+        assert_eq!(Matrix2d::from_csv(&out[..], &CsvOptions::default()).unwrap(), m);
+
+        // transposed views are written in logical order
+        let mut out = Vec::new();
+        let options = CsvOptions { delimiter: b'\t', precision: Some(2), ..CsvOptions::default() };
+        m.transpose().to_csv(&mut out, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "0.10\t0.33\n-0.00\t100000000000000000000.00\n");
+    }
+}
+
+mod npy {
+    extern crate flate2;
+
+    use self::flate2::Compression;
+    use self::flate2::Crc;
+    use self::flate2::write::DeflateEncoder;
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::io::npy::*;
+    use std::io::{Cursor, Write};
+
+    fn npy_bytes(descr: &str, fortran: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let header = format!("{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}\n",
+                             descr, if fortran { "True" } else { "False" }, shape);
+        let mut out = b"\x93NUMPY\x01\x00".to_vec();
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    // a minimal zip archive, the way `np.savez` lays it out
+    fn zip_bytes(entries: &[(&str, Vec<u8>, bool)]) -> Vec<u8> {
+        let (mut out, mut dir) = (Vec::new(), Vec::new());
+        for &(name, ref data, deflate) in entries.iter() {
+            let body = if deflate {
+                let mut e = DeflateEncoder::new(Vec::new(), Compression::default());
+                e.write_all(data).unwrap();
+                e.finish().unwrap()
+            } else {
+                data.clone()
+            };
+            let mut crc = Crc::new();
+            crc.update(data);
+
+            let mut common = Vec::new();
+            common.extend_from_slice(&[20, 0, 0, 0]);
+            common.extend_from_slice(&(if deflate { 8u16 } else { 0 }).to_le_bytes());
+            common.extend_from_slice(&[0, 0, 0, 0]);
+            common.extend_from_slice(&crc.sum().to_le_bytes());
+            common.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&[0, 0]);
+
+            dir.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            dir.extend_from_slice(&[20, 0]);
+            dir.extend_from_slice(&common);
+            dir.extend_from_slice(&[0; 10]);
+            dir.extend_from_slice(&(out.len() as u32).to_le_bytes());
+            dir.extend_from_slice(name.as_bytes());
+
+            out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            out.extend_from_slice(&common);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&body);
+        }
+        let dir_offset = out.len() as u32;
+        out.extend_from_slice(&dir);
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(dir.len() as u32).to_le_bytes());
+        out.extend_from_slice(&dir_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn reads_dtypes_and_orders() {
+        let data = [1f32, 2., 3., 4., 5., 6.].iter().flat_map(|v| v.to_be_bytes().to_vec()).collect::<Vec<u8>>();
+        let m = Matrix2d::read_npy(&npy_bytes(">f4", false, "(2, 3)", &data)[..]).unwrap();
+        assert_eq!(m, vec![vec![1., 2., 3.], vec![4., 5., 6.]].to_matrix_2d().unwrap());
+
+        // column-major buffer, read as is and described by the strides
+        let data = [1i64, 4, 2, 5, 3, 6].iter().flat_map(|v| v.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+        let m = Matrix2d::read_npy(&npy_bytes("<i8", true, "(2, 3)", &data)[..]).unwrap();
+        assert_eq!((m.get_row_stride(), m.get_col_stride()), (1, 2));
+        assert_eq!(m.get_row(1).unwrap(), vec![4., 5., 6.]);
+
+        let data = [0.5f64, -1.].iter().flat_map(|v| v.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+        let m = Matrix2d::read_npy(&npy_bytes("<f8", false, "(2,)", &data)[..]).unwrap();
+        assert_eq!(m, vec![0.5, -1.].to_matrix_2d().unwrap());
+
+        assert!(Matrix2d::read_npy(&npy_bytes("<u2", false, "(1,)", &[0, 0])[..]).is_err());
+        assert!(Matrix2d::read_npy(&npy_bytes("<f8", false, "(2, 3)", &data)[..]).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let m = vec![vec![1.5, -2., 3.], vec![4., 5.25, -6.]].to_matrix_2d().unwrap();
+
+        for &dtype in [Dtype::F64(Endian::Little), Dtype::F64(Endian::Big), Dtype::F32(Endian::Big)].iter() {
+            let mut out = Vec::new();
+            m.write_npy(&mut out, dtype).unwrap();
+            assert_eq!((out.iter().position(|&b| b == b'\n').unwrap() + 1) % 64, 0);
+            assert_eq!(Matrix2d::read_npy(&out[..]).unwrap(), m);
+        }
+
+        let mut out = Vec::new();
+        m.transpose().write_npy(&mut out, Dtype::I64(Endian::Little)).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("'fortran_order': True"));
+        let t = Matrix2d::read_npy(&out[..]).unwrap();
+        assert_eq!(t.get_row(0).unwrap(), vec![1., 4.]);
+        assert_eq!(t.get_row(2).unwrap(), vec![3., -6.]);
+    }
+
+    #[test]
+    fn reads_npz_entries() {
+        let a = vec![vec![1., 2.], vec![3., 4.]].to_matrix_2d().unwrap();
+        let b = vec![vec![-1., 0.5, 8.]].to_matrix_2d().unwrap();
+        let (mut a_npy, mut b_npy) = (Vec::new(), Vec::new());
+        a.write_npy(&mut a_npy, Dtype::F64(Endian::Little)).unwrap();
+        b.write_npy(&mut b_npy, Dtype::F32(Endian::Little)).unwrap();
+
+        let archive = zip_bytes(&[("a.npy", a_npy, false), ("b.npy", b_npy, true)]);
+        assert_eq!(npz_names(Cursor::new(&archive)).unwrap(), vec!["a", "b"]);
+        assert_eq!(Matrix2d::read_npz(Cursor::new(&archive), "a").unwrap(), a);
+        assert_eq!(Matrix2d::read_npz(Cursor::new(&archive), "b.npy").unwrap(), b);
+        assert!(Matrix2d::read_npz(Cursor::new(&archive), "c").is_err());
+
+        // a flipped bit in the stored array's data fails the checksum
+        let mut corrupt = archive.clone();
+        corrupt[30 + "a.npy".len() + 130] ^= 0x01;
+        assert!(Matrix2d::read_npz(Cursor::new(&corrupt), "a").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_headers_and_archives() {
+        assert!(Matrix2d::read_npy(&npy_bytes("", false, "(1,)", &[0; 8])[..]).is_err());
+        assert!(Matrix2d::read_npy(&npy_bytes("é", false, "(1,)", &[0; 8])[..]).is_err());
+        assert!(Matrix2d::read_npy(&npy_bytes("<f8", false, "(4294967296, 4294967296)", &[0; 8])[..]).is_err());
+
+        let mut npy = Vec::new();
+        vec![1., 2.].to_matrix_2d().unwrap().write_npy(&mut npy, Dtype::F64(Endian::Little)).unwrap();
+        let archive = zip_bytes(&[("a.npy", npy, false)]);
+
+        // central directory size past the end of the archive
+        let mut bad = archive.clone();
+        let at = bad.len() - 10;
+        bad[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(npz_names(Cursor::new(&bad)).is_err());
+
+        // entry name length past the end of the central directory
+        let mut bad = archive.clone();
+        let dir_offset = u32::from_le_bytes([bad[at + 4], bad[at + 5], bad[at + 6], bad[at + 7]]) as usize;
+        bad[dir_offset + 28..dir_offset + 30].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(npz_names(Cursor::new(&bad)).is_err());
+    }
+}
+
+mod mtx {
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::io::mtx::*;
+    use num_rust::sparse::CsrMatrix;
+
+    #[test]
+    fn reads_array_variants() {
+        let text = "%%MatrixMarket matrix array real general\n% a comment\n2 3\n1\n4\n2\n5\n3\n6.5\n";
+        let m = Matrix2d::read_mtx(text.as_bytes()).unwrap();
+        assert_eq!(m, vec![vec![1., 2., 3.], vec![4., 5., 6.5]].to_matrix_2d().unwrap());
+
+        let text = "%%MatrixMarket matrix array integer symmetric\n2 2\n1\n2\n3\n";
+        let m = Matrix2d::read_mtx(text.as_bytes()).unwrap();
+        assert_eq!(m, vec![vec![1., 2.], vec![2., 3.]].to_matrix_2d().unwrap());
+
+        let text = "%%MatrixMarket matrix array real skew-symmetric\n3 3\n1\n2\n3\n";
+        let m = Matrix2d::read_mtx(text.as_bytes()).unwrap();
+        assert_eq!(m, vec![vec![0., -1., -2.], vec![1., 0., -3.], vec![2., 3., 0.]].to_matrix_2d().unwrap());
+
+        assert!(Matrix2d::read_mtx("%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n".as_bytes()).is_err());
+        assert!(Matrix2d::read_mtx("%%MatrixMarket matrix array complex general\n1 1\n1 0\n".as_bytes()).is_err());
+
+        // sizes that overflow or are too large to densify
+        for size in ["100000000000 100000000000 0", "3000000 3000000 0"].iter() {
+            let text = format!("%%MatrixMarket matrix coordinate real general\n{}\n", size);
+            let err = Matrix2d::read_mtx(text.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+        let text = "%%MatrixMarket matrix coordinate real general\n100000000000 1 0\n";
+        assert!(CsrMatrix::read_mtx(text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn reads_coordinate_variants() {
+        let text = "%%MatrixMarket matrix coordinate real symmetric\n3 3 3\n1 1 2.5\n3 1 -1\n2 2 4\n";
+        let s = CsrMatrix::read_mtx(text.as_bytes()).unwrap();
+        assert_eq!(s.nnz(), 4);
+        assert_eq!(s.get(0, 2), Some(-1.));
+        assert_eq!(Matrix2d::read_mtx(text.as_bytes()).unwrap(), s.to_dense());
+
+        let text = "%%MatrixMarket matrix coordinate pattern general\n2 3 2\n1 3\n2 1\n";
+        let m = Matrix2d::read_mtx(text.as_bytes()).unwrap();
+        assert_eq!(m, vec![vec![0., 0., 1.], vec![1., 0., 0.]].to_matrix_2d().unwrap());
+
+        let text = "%%MatrixMarket matrix coordinate integer general\n2 2 1\n3 1 7\n";
+        assert!(CsrMatrix::read_mtx(text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let sym = vec![vec![2., -1., 0.], vec![-1., 2., -1.], vec![0., -1., 2.]].to_matrix_2d().unwrap();
+        for &symmetry in [Symmetry::General, Symmetry::Symmetric].iter() {
+            let mut out = Vec::new();
+            sym.write_mtx(&mut out, Field::Real, symmetry).unwrap();
+            assert_eq!(Matrix2d::read_mtx(&out[..]).unwrap(), sym);
+
+            let sparse = CsrMatrix::from_dense(&sym);
+            let mut out = Vec::new();
+            sparse.write_mtx(&mut out, Field::Integer, symmetry).unwrap();
+            assert_eq!(CsrMatrix::read_mtx(&out[..]).unwrap(), sparse);
+        }
+
+        let mut out = Vec::new();
+        CsrMatrix::from_dense(&sym).write_mtx(&mut out, Field::Real, Symmetry::Symmetric).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("\n3 3 5\n"));
+
+        let skew = vec![vec![0., 1.5], vec![-1.5, 0.]].to_matrix_2d().unwrap();
+        let mut out = Vec::new();
+        skew.transpose().write_mtx(&mut out, Field::Real, Symmetry::SkewSymmetric).unwrap();
+        let back = Matrix2d::read_mtx(&out[..]).unwrap();
+        assert_eq!(back.get_row(0).unwrap(), vec![0., -1.5]);
+        assert_eq!(back.get_row(1).unwrap(), vec![1.5, 0.]);
+
+        assert!(skew.write_mtx(Vec::new(), Field::Real, Symmetry::Symmetric).is_err());
+        assert!(sym.write_mtx(Vec::new(), Field::Pattern, Symmetry::General).is_err());
+    }
+}
+
+mod checkpoint {
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::io::checkpoint::*;
+    use std::env;
+    use std::fs;
+
+    fn weights() -> (Matrix2d, Matrix2d) {
+        let w = vec![vec![0.1, -0.2, 0.3], vec![1e-8, 5., -6.5]].to_matrix_2d().unwrap();
+        let b = vec![vec![0.5, 0.25, -1.]].to_matrix_2d().unwrap();
+        (w, b)
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let (w, b) = weights();
+        let path = env::temp_dir().join(format!("num_rust_checkpoint_{}.bin", ::std::process::id()));
+        save(&path, &[("dense1/w", &w), ("dense1/b", &b)]).unwrap();
+
+        let ckpt = Checkpoint::open(&path).unwrap();
+        assert_eq!(ckpt.names(), vec!["dense1/w", "dense1/b"]);
+        assert_eq!(ckpt.get("dense1/w").unwrap(), w);
+        assert_eq!(ckpt.get("dense1/b").unwrap(), b);
+        assert_eq!(ckpt.entries()[0].get_shape(), (2, 3));
+        assert_eq!(ckpt.get("missing").unwrap_err().kind(), ::std::io::ErrorKind::NotFound);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_strides_and_dtypes() {
+        let (w, b) = weights();
+        let mut writer = CheckpointWriter::new(Vec::new()).unwrap();
+        writer.write_as("b", &b, Dtype::F32).unwrap();
+        writer.write("wt", &w.transpose()).unwrap();
+        assert!(writer.write("b", &w).is_err());
+        let bytes = writer.finish().unwrap();
+
+        let ckpt = Checkpoint::from_bytes(bytes).unwrap();
+        assert_eq!(ckpt.entries()[0].get_dtype(), Dtype::F32);
+        assert_eq!(ckpt.get("b").unwrap().get_row(0).unwrap(), vec![0.5, 0.25, -1.]);
+
+        let wt = ckpt.get("wt").unwrap();
+        assert_eq!(ckpt.entries()[1].get_strides(), (1, 3));
+        assert_eq!(wt.get_row(2).unwrap(), vec![0.3, -6.5]);
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let (w, b) = weights();
+        let mut writer = CheckpointWriter::new(Vec::new()).unwrap();
+        writer.write("w", &w).unwrap();
+        writer.write("b", &b).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        // a flipped bit in the first entry's data only fails that entry
+        let mut corrupt = bytes.clone();
+        corrupt[16 + 3] ^= 0x10;
+        let ckpt = Checkpoint::from_bytes(corrupt).unwrap();
+        assert!(ckpt.get("w").is_err());
+        assert_eq!(ckpt.get("b").unwrap(), b);
+
+        // damaging the index fails the open
+        let mut corrupt = bytes.clone();
+        let at = corrupt.len() - 30;
+        corrupt[at] ^= 0x01;
+        assert!(Checkpoint::from_bytes(corrupt).is_err());
+
+        let mut future = bytes.clone();
+        future[8] = 2;
+        assert!(Checkpoint::from_bytes(future).is_err());
+        assert!(Checkpoint::from_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+    }
+}
+
+mod mmap {
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::io::mmap::MmapMatrix;
+    use num_rust::io::npy::{Dtype, Endian};
+    use num_rust::utils::Layout;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("num_rust_mmap_{}_{}", ::std::process::id(), name))
+    }
+
+    fn data() -> Matrix2d {
+        (0..7).map(|r| (0..3).map(|c| (r * 3 + c) as f64 * 0.5).collect()).collect::<Vec<Vec<f64>>>()
+            .to_matrix_2d()
+            .unwrap()
+    }
+
+    #[test]
+    fn npy_views_and_products() {
+        let m = data();
+        let path = temp_path("views.npy");
+        m.write_npy(File::create(&path).unwrap(), Dtype::F64(Endian::Little)).unwrap();
+
+        let mm = MmapMatrix::open_npy(&path).unwrap();
+        assert_eq!((mm.get_rows(), mm.get_cols()), (7, 3));
+        assert_eq!(mm.get_row(4), m.get_row(4));
+        assert_eq!(mm.get_col(2), m.get_col(2));
+        assert_eq!(mm.to_matrix_2d().unwrap(), m);
+
+        let view = mm.rows(2, 5).unwrap().cols(1, 3).unwrap();
+        assert_eq!((view.get_rows(), view.get_cols()), (3, 2));
+        assert_eq!(view.get_row(0).unwrap(), vec![3.5, 4.]);
+        assert_eq!(view.transpose().get_row(1).unwrap(), vec![4., 5.5, 7.]);
+        assert!(mm.rows(5, 8).is_none());
+
+        let w = vec![vec![1., -1.], vec![0.5, 2.], vec![-3., 0.]].to_matrix_2d().unwrap();
+        assert_eq!(mm.dot(&w).unwrap(), m.dot(&w).unwrap());
+        assert!(mm.dot(&m).is_none());
+        let empty = Matrix2d::from_vec_with_layout(Vec::new(), 0, 2, Layout::RowMajor).unwrap();
+        assert_eq!(mm.cols(1, 1).unwrap().dot(&empty).unwrap(), Matrix2d::new(7, 2));
+        assert_eq!(mm.dot(&Matrix2d::new(3, 0)).unwrap().get_rows(), 7);
+
+        let batches = mm.mini_batch(3).collect::<Vec<Matrix2d>>();
+        assert_eq!(batches.iter().map(|b| b.get_rows()).collect::<Vec<usize>>(), vec![3, 3, 1]);
+        assert_eq!(batches[1].get_row(0), m.get_row(3));
+
+        drop(mm);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fortran_npy_and_raw_files() {
+        let m = data();
+        let path = temp_path("fortran.npy");
+        m.transpose().write_npy(File::create(&path).unwrap(), Dtype::F32(Endian::Big)).unwrap();
+
+        let mm = MmapMatrix::open_npy(&path).unwrap();
+        assert_eq!((mm.get_row_stride(), mm.get_col_stride()), (1, 3));
+        assert_eq!(mm.get_row(1).unwrap(), m.get_col(1).unwrap());
+        assert_eq!(mm.transpose().to_matrix_2d().unwrap(), m);
+        fs::remove_file(&path).unwrap();
+
+        let path = temp_path("raw.bin");
+        let bytes = m.ravel().iter().flat_map(|v| v.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+        File::create(&path).unwrap().write_all(&bytes).unwrap();
+        let raw = MmapMatrix::open_raw(&path, 7, 3, Dtype::F64(Endian::Little)).unwrap();
+        assert_eq!(raw.get(6, 2), Some(10.));
+        assert_eq!(raw.reshape(3, 7).unwrap().get_row(0).unwrap()[6], 3.);
+        assert!(MmapMatrix::open_raw(&path, 8, 3, Dtype::F64(Endian::Little)).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::linear_model::regression::*;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-8
+}
+
+// y = 3 + 2 x0 - x1
+fn dataset() -> (Matrix2d, Matrix2d) {
+    let x = vec![vec![1., 2.], vec![2., 1.], vec![3., 5.], vec![4., 3.], vec![5., 0.], vec![0., 1.]];
+    let y = x.iter().map(|r| 3. + 2. * r[0] - r[1]).collect::<Vec<f64>>();
+    (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+}
+
+#[test]
+fn linear_regression_recovers_coefficients() {
+    let (x, y) = dataset();
+    let mut lr = LinearRegression::new(true);
+    lr.fit(&x, &y).unwrap();
+
+    let coef = lr.get_coefficients().unwrap().ravel();
+    assert!(close(coef[0], 2.) && close(coef[1], -1.));
+    assert!(close(lr.get_intercept().unwrap().get_matrix()[0], 3.));
+    assert!(close(lr.score(&x, &y).unwrap(), 1.));
+
+    let new = vec![vec![10., 10.]].to_matrix_2d().unwrap();
+    assert!(close(lr.predict(&new).unwrap().get_matrix()[0], 13.));
+}
+
+#[test]
+fn linear_regression_without_intercept() {
+    let x = vec![vec![1.], vec![2.], vec![3.]].to_matrix_2d().unwrap();
+    let y = vec![vec![2., -1.], vec![4., -2.], vec![6., -3.]].to_matrix_2d().unwrap();
+
+    let mut lr = LinearRegression::new(false);
+    lr.fit(&x, &y).unwrap();
+    let coef = lr.get_coefficients().unwrap().ravel();
+    assert!(close(coef[0], 2.) && close(coef[1], -1.));
+    assert!(lr.get_intercept().unwrap().ravel() == vec![0., 0.]);
+}
+
+#[test]
+fn linear_regression_rejects_bad_input() {
+    let (x, _) = dataset();
+    let mut lr = LinearRegression::new(true);
+    assert!(lr.predict(&x).is_none());
+
+    let short = vec![1., 2.].to_matrix_2d().unwrap();
+    assert!(lr.fit(&x, &short).is_none());
+
+    // second column is a copy of the first
+    let collinear = vec![vec![1., 1.], vec![2., 2.], vec![3., 3.]].to_matrix_2d().unwrap();
+    assert!(lr.fit(&collinear, &vec![1., 2., 3.].to_matrix_2d().unwrap()).is_none());
+}
+
+#[test]
+fn ridge_shrinks_towards_zero() {
+    let (x, y) = dataset();
+    let mut small = Ridge::new(1e-10, true);
+    small.fit(&x, &y).unwrap();
+    let coef = small.get_coefficients().unwrap().ravel();
+    assert!((coef[0] - 2.).abs() < 1e-6);
+
+    let mut big = Ridge::new(100., true);
+    big.fit(&x, &y).unwrap();
+    let shrunk = big.get_coefficients().unwrap().ravel();
+    assert!(shrunk[0].abs() < 2. && shrunk[1].abs() < 1.);
+
+    // handles collinear features that plain least squares rejects
+    let collinear = vec![vec![1., 1.], vec![2., 2.], vec![3., 3.]].to_matrix_2d().unwrap();
+    let mut ridge = Ridge::new(1., false);
+    ridge.fit(&collinear, &vec![2., 4., 6.].to_matrix_2d().unwrap()).unwrap();
+    let coef = ridge.get_coefficients().unwrap().ravel();
+    assert!(close(coef[0], coef[1]));
+}
+
+#[test]
+fn ridge_matches_closed_form() {
+    let x = vec![vec![1.], vec![2.], vec![3.]].to_matrix_2d().unwrap();
+    let y = vec![1., 2., 2.].to_matrix_2d().unwrap();
+    let mut ridge = Ridge::new(2., false);
+    ridge.fit(&x, &y).unwrap();
+
+    // (x^T y) / (x^T x + alpha) = 11 / 16
+    assert!(close(ridge.get_coefficients().unwrap().get_matrix()[0], 11. / 16.));
+}
+
+#[test]
+fn lasso_selects_features() {
+    let (x, y) = dataset();
+    let mut lasso = Lasso::new(1e-6, true);
+    lasso.fit(&x, &y).unwrap();
+    let coef = lasso.get_coefficients().unwrap().ravel();
+    assert!((coef[0] - 2.).abs() < 1e-3 && (coef[1] + 1.).abs() < 1e-3);
+    assert!(lasso.get_n_iter() > 0);
+
+    let mut sparse = Lasso::new(3., true);
+    sparse.fit(&x, &y).unwrap();
+    let coef = sparse.get_coefficients().unwrap().ravel();
+    assert!(coef[1] == 0. && coef[0] > 0.);
+
+    let mut empty = Lasso::new(1e3, true);
+    empty.fit(&x, &y).unwrap();
+    assert!(empty.get_coefficients().unwrap().ravel() == vec![0., 0.]);
+    assert!(close(empty.get_intercept().unwrap().get_matrix()[0], y.ravel().iter().sum::<f64>() / 6.));
+}
+
+mod logistic {
+    use num_rust::Matrix2d;
+    use num_rust::ext::traits::ToMatrix2d;
+    use num_rust::linear_model::logistic::*;
+
+    // overlapping classes so the unpenalized optimum is finite
+    fn binary() -> (Matrix2d, Matrix2d) {
+        let x = vec![vec![0.5, 1.], vec![1., 1.5], vec![1.5, 0.5], vec![2., 2.5], vec![2.5, 2.],
+                     vec![3., 3.5], vec![3.5, 2.], vec![4., 4.], vec![2.2, 1.8], vec![1.8, 2.2]];
+        let y = vec![5., 5., 5., 7., 5., 7., 7., 7., 7., 5.];
+        (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+    }
+
+    fn blobs() -> (Matrix2d, Matrix2d) {
+        let centers = [(0., 0.), (4., 0.), (0., 4.)];
+        let offsets = [(0.3, -0.2), (-0.4, 0.1), (0.2, 0.5), (-0.1, -0.6)];
+        let mut x = Vec::new();
+        let mut y = Vec::new();
+        for (c, &(cx, cy)) in centers.iter().enumerate() {
+            for &(dx, dy) in offsets.iter() {
+                x.push(vec![cx + dx, cy + dy]);
+                y.push(c as f64);
+            }
+        }
+        (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+    }
+
+    #[test]
+    fn binary_solvers_agree() {
+        let (x, y) = binary();
+        let mut gd = LogisticRegression::with_params(Penalty::L2(0.01), Solver::GradientDescent { learning_rate: 0.5 }, 20000, 1e-10);
+        let mut lbfgs = LogisticRegression::new(Penalty::L2(0.01), Solver::Lbfgs { memory: 10 });
+        gd.fit(&x, &y).unwrap();
+        lbfgs.fit(&x, &y).unwrap();
+
+        assert!(lbfgs.get_n_iter() < gd.get_n_iter());
+        for (a, b) in gd.get_coefficients().unwrap().ravel().iter().zip(lbfgs.get_coefficients().unwrap().ravel().iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+        assert!(lbfgs.get_classes() == [5., 7.]);
+        assert!(lbfgs.score(&x, &y).unwrap() >= 0.8);
+
+        let proba = lbfgs.predict_proba(&x).unwrap();
+        let logits = lbfgs.decision_function(&x).unwrap();
+        assert!(proba.get_cols() == 2 && logits.get_cols() == 1);
+        for r in 0..x.get_rows() {
+            let p = proba.get_row(r).unwrap();
+            assert!((p[0] + p[1] - 1.).abs() < 1e-12);
+            assert!((p[1] > 0.5) == (logits.get_row(r).unwrap()[0] > 0.));
+        }
+    }
+
+    #[test]
+    fn multinomial() {
+        let (x, y) = blobs();
+        let mut clf = LogisticRegression::new(Penalty::L2(0.1), Solver::Lbfgs { memory: 5 });
+        clf.fit(&x, &y).unwrap();
+
+        assert!(clf.predict(&x).unwrap() == y);
+        assert!(clf.get_coefficients().unwrap().get_cols() == 3);
+
+        let new = vec![vec![4.2, 0.3], vec![-0.5, 4.5]].to_matrix_2d().unwrap();
+        assert!(clf.predict(&new).unwrap().ravel() == vec![1., 2.]);
+
+        let proba = clf.predict_proba(&new).unwrap();
+        assert!((proba.get_row(0).unwrap().iter().sum::<f64>() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn l1_zeroes_noise_features() {
+        let (x, y) = binary();
+        // third column is noise
+        let noisy = (0..x.get_rows())
+            .map(|r| {
+                let mut row = x.get_row(r).unwrap();
+                row.push(((r * 7) % 5) as f64 * 0.01);
+                row
+            })
+            .collect::<Vec<Vec<f64>>>()
+            .to_matrix_2d()
+            .unwrap();
+
+        let mut owlqn = LogisticRegression::new(Penalty::L1(0.05), Solver::Lbfgs { memory: 10 });
+        owlqn.fit(&noisy, &y).unwrap();
+        let coef = owlqn.get_coefficients().unwrap().ravel();
+        assert!(coef[2] == 0.);
+        assert!(coef[0] != 0. || coef[1] != 0.);
+
+        let mut ista = LogisticRegression::with_params(Penalty::L1(0.05), Solver::GradientDescent { learning_rate: 0.5 }, 20000, 1e-10);
+        ista.fit(&noisy, &y).unwrap();
+        let ista_coef = ista.get_coefficients().unwrap().ravel();
+        assert!(ista_coef[2] == 0.);
+        for (a, b) in coef.iter().zip(ista_coef.iter()) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let (x, _) = binary();
+        let mut clf = LogisticRegression::new(Penalty::None, Solver::Lbfgs { memory: 5 });
+        assert!(clf.predict(&x).is_none());
+        assert!(clf.fit(&x, &Matrix2d::new(10, 1)).is_none());
+        assert!(clf.fit(&x, &Matrix2d::new(3, 1)).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::loss::*;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+fn numeric_grad<F>(pred: &Matrix2d, f: F) -> Vec<f64>
+    where F: Fn(&Matrix2d) -> f64
+{
+    let h = 1e-6;
+    let base = pred.ravel();
+    (0..base.len())
+        .map(|i| {
+            let mut up = base.clone();
+            let mut down = base.clone();
+            up[i] += h;
+            down[i] -= h;
+            let up = up.reshape(pred.get_rows(), pred.get_cols()).unwrap();
+            let down = down.reshape(pred.get_rows(), pred.get_cols()).unwrap();
+            (f(&up) - f(&down)) / (2. * h)
+        })
+        .collect::<Vec<f64>>()
+}
+
+fn assert_grad<F>(pred: &Matrix2d, grad: &Matrix2d, f: F)
+    where F: Fn(&Matrix2d) -> f64
+{
+    let numeric = numeric_grad(pred, f);
+    for (a, n) in grad.ravel().iter().zip(numeric.iter()) {
+        assert!((a - n).abs() < 1e-4, "analytic {} vs numeric {}", a, n);
+    }
+}
+
+#[test]
+fn mean_squared_error_test() {
+    let p = vec![vec![1., 2.], vec![3., 4.]].to_matrix_2d().unwrap();
+    let t = vec![vec![1., 0.], vec![3., 6.]].to_matrix_2d().unwrap();
+
+    let (l, g) = mean_squared_error(&p, &t).unwrap();
+    assert!(close(l, 2.));
+    assert!(g == vec![vec![0., 1.], vec![0., -1.]].to_matrix_2d().unwrap());
+}
+
+#[test]
+fn shape_mismatch() {
+    let p = vec![1., 2.].to_matrix_2d().unwrap();
+    let t = vec![1., 2., 3.].to_matrix_2d().unwrap();
+
+    assert!(mean_squared_error(&p, &t).is_none());
+    assert!(categorical_cross_entropy_with_logits(&p, &t).is_none());
+}
+
+#[test]
+fn binary_cross_entropy_test() {
+    let p = vec![0.9, 0.2, 0.6].to_matrix_2d().unwrap();
+    let t = vec![1., 0., 1.].to_matrix_2d().unwrap();
+
+    let (_, g) = binary_cross_entropy(&p, &t).unwrap();
+    assert_grad(&p, &g, |m| binary_cross_entropy(m, &t).unwrap().0);
+}
+
+#[test]
+fn binary_cross_entropy_with_logits_matches_probabilities() {
+    let z = vec![2.0, -1.5, 0.3].to_matrix_2d().unwrap();
+    let t = vec![1., 0., 0.].to_matrix_2d().unwrap();
+    let p = z.apply_fn(|x| 1. / (1. + (-x).exp()));
+
+    let (l, g) = binary_cross_entropy_with_logits(&z, &t).unwrap();
+    assert!(close(l, binary_cross_entropy(&p, &t).unwrap().0));
+    assert_grad(&z, &g, |m| binary_cross_entropy_with_logits(m, &t).unwrap().0);
+
+    let big = vec![1000., -1000.].to_matrix_2d().unwrap();
+    let t = vec![0., 1.].to_matrix_2d().unwrap();
+    assert!(close(binary_cross_entropy_with_logits(&big, &t).unwrap().0, 1000.));
+}
+
+#[test]
+fn categorical_cross_entropy_test() {
+    let z = vec![vec![1., 2., 0.5], vec![-1., 0., 3.]].to_matrix_2d().unwrap();
+    let t = vec![vec![0., 1., 0.], vec![1., 0., 0.]].to_matrix_2d().unwrap();
+
+    let (l, g) = categorical_cross_entropy_with_logits(&z, &t).unwrap();
+    assert_grad(&z, &g, |m| categorical_cross_entropy_with_logits(m, &t).unwrap().0);
+
+    let mut probs = Vec::new();
+    for row in 0..z.get_rows() {
+        let r = z.get_row(row).unwrap();
+        let s: f64 = r.iter().map(|x| x.exp()).sum();
+        probs.push(r.iter().map(|x| x.exp() / s).collect::<Vec<f64>>());
+    }
+    let p = probs.to_matrix_2d().unwrap();
+
+    let (pl, pg) = categorical_cross_entropy(&p, &t).unwrap();
+    assert!(close(l, pl));
+    assert_grad(&p, &pg, |m| categorical_cross_entropy(m, &t).unwrap().0);
+}
+
+#[test]
+fn hinge_test() {
+    let p = vec![0.5, 2., -0.3].to_matrix_2d().unwrap();
+    let t = vec![1., 1., -1.].to_matrix_2d().unwrap();
+
+    let (l, g) = hinge(&p, &t).unwrap();
+    assert!(close(l, (0.5 + 0. + 0.7) / 3.));
+    assert!(g == vec![-1. / 3., 0., 1. / 3.].to_matrix_2d().unwrap());
+}
+
+#[test]
+fn huber_test() {
+    let p = vec![0.5, 3., -4.].to_matrix_2d().unwrap();
+    let t = vec![0., 0., 0.].to_matrix_2d().unwrap();
+
+    let (l, g) = huber(&p, &t, 1.).unwrap();
+    assert!(close(l, (0.125 + 2.5 + 3.5) / 3.));
+    assert_grad(&p, &g, |m| huber(m, &t, 1.).unwrap().0);
+}
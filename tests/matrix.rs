@@ -1,6 +1,8 @@
 extern crate num_rust;
 
 use num_rust::ext::traits::ToMatrix2d;
+use num_rust::Matrix2d;
+use num_rust::utils::{Axis, Layout, Norm, set_par_chunk_size};
 
 #[test]
 fn to_matrix_2d_vec() {
@@ -147,3 +149,181 @@ fn reshape_1d() {
 
     assert!(m == rm);
 }
+
+#[test]
+fn par_elementwise() {
+    let m = vec![vec![-1f64, 2f64, 0f64], vec![0f64, 3f64, 6f64]].to_matrix_2d().unwrap();
+    let m1 = vec![vec![0f64, -4f64, 3f64], vec![9f64, -4f64, -3f64]].to_matrix_2d().unwrap();
+
+    assert!(m.par_addition(&m1).unwrap() == m.addition(&m1).unwrap());
+    assert!(m.par_subtract(&m1).unwrap() == m.subtract(&m1).unwrap());
+    assert!(m.par_mult(&m1).unwrap() == m.mult(&m1).unwrap());
+    assert!(m.par_scale(-0.5) == m.scale(-0.5));
+    assert!(m.par_addition(&m1.transpose()).is_none());
+
+    // a borrowed, non-'static closure
+    let offset = 10f64;
+    assert!(m.par_apply_fn(&|x| x + offset) == m.apply_fn(|x| x + offset));
+}
+
+#[test]
+fn par_fn_small_chunks() {
+    let m = vec![vec![1f64, -2f64, 3f64]].to_matrix_2d().unwrap();
+
+    // chunks of a single element still split down to the leaves
+    for &chunk_size in [Some(0), Some(1), None].iter() {
+        set_par_chunk_size(chunk_size);
+        assert!(m.par_scale(2f64) == m.scale(2f64));
+        assert!(m.par_apply_fn(&|x| x * x) == m.apply_fn(|x| x * x));
+    }
+}
+
+#[test]
+fn par_elementwise_mixed_layouts() {
+    let m = vec![vec![1f64, 2f64], vec![3f64, 4f64], vec![5f64, 6f64]].to_matrix_2d().unwrap();
+    let t = vec![vec![10f64, 30f64, 50f64], vec![20f64, 40f64, 60f64]].to_matrix_2d().unwrap().transpose();
+
+    let sum = m.par_addition(&t).unwrap();
+    assert!(sum.get_row(2).unwrap() == vec![55f64, 66f64]);
+
+    let sum = t.par_addition(&m).unwrap();
+    assert!(sum.get_row(1).unwrap() == vec![33f64, 44f64]);
+}
+
+#[test]
+fn par_elementwise_large() {
+    let n = 300;
+    let m = (0..n).map(|i| (0..n).map(|j| (i * n + j) as f64).collect()).collect::<Vec<Vec<f64>>>()
+        .to_matrix_2d()
+        .unwrap();
+    let sum = m.par_addition(&m).unwrap();
+    assert!(sum == m.scale(2f64));
+    assert!(m.par_mult(&m.transpose()).unwrap().get_row(1).unwrap()[0] == (n as f64) * 1f64);
+}
+
+#[test]
+fn par_reductions() {
+    let m = vec![vec![-1f64, 2f64, 0f64], vec![4f64, 3f64, -6f64]].to_matrix_2d().unwrap();
+    let t = m.transpose();
+
+    assert!(m.par_sum() == 2f64 && t.par_sum() == 2f64);
+    assert!(m.par_norm(Norm::L1) == 16f64);
+    assert!(m.par_norm(Norm::L2) == 66f64.sqrt());
+    assert!(t.par_norm(Norm::Max) == 6f64);
+    assert!(m.par_inner(&m).unwrap() == 66f64);
+    assert!(t.par_inner(&vec![vec![1f64, 0f64], vec![0f64, 1f64], vec![1f64, 1f64]].to_matrix_2d().unwrap()).unwrap() == -4f64);
+    assert!(m.par_inner(&t).is_none());
+}
+
+#[test]
+fn par_axis_reductions() {
+    let m = vec![vec![-1f64, 2f64, 0f64], vec![4f64, 3f64, -6f64]].to_matrix_2d().unwrap();
+
+    assert!(m.par_sum_axis(Axis::Cols) == vec![vec![3f64, 5f64, -6f64]].to_matrix_2d().unwrap());
+    assert!(m.par_sum_axis(Axis::Rows) == vec![1f64, 1f64].to_matrix_2d().unwrap());
+    assert!(m.par_mean_axis(Axis::Cols).get_matrix() == &vec![1.5f64, 2.5f64, -3f64]);
+    assert!(m.par_max_axis(Axis::Rows).get_matrix() == &vec![2f64, 4f64]);
+
+    // a transpose reduces along the other axis
+    let t = m.transpose();
+    assert!(t.par_min_axis(Axis::Rows).get_matrix() == m.par_min_axis(Axis::Cols).get_matrix());
+    assert!(t.par_sum_axis(Axis::Cols).get_matrix() == &vec![1f64, 1f64]);
+}
+
+#[test]
+fn par_dot_matches_serial() {
+    let a = (0..300).map(|i| (0..200).map(|j| ((i * 7 + j * 13) % 17) as f64 / 3f64).collect()).collect::<Vec<Vec<f64>>>()
+        .to_matrix_2d()
+        .unwrap();
+    let b = (0..150).map(|i| (0..200).map(|j| ((i * 5 + j * 3) % 11) as f64 / 7f64).collect()).collect::<Vec<Vec<f64>>>()
+        .to_matrix_2d()
+        .unwrap()
+        .transpose();
+
+    let serial = a.dot_serial(&b).unwrap();
+    assert!(a.par_dot(&b).unwrap() == serial);
+    assert!(a.dot(&b).unwrap() == serial);
+    assert!(a.par_dot(&a).is_none() && a.dot_serial(&a).is_none());
+
+    // blocks of a column-major product
+    let product = a.to_layout(Layout::ColMajor).par_dot(&b.to_layout(Layout::ColMajor)).unwrap();
+    assert!(product.layout() == Some(Layout::ColMajor) && product == serial);
+
+    // thin outputs split along their only long side
+    let col = b.get_col(0).unwrap().to_matrix_2d().unwrap();
+    assert!(a.par_dot(&col).unwrap().get_matrix() == serial.get_col(0).unwrap().as_slice());
+}
+
+#[test]
+fn views_share_buffer() {
+    let m = vec![vec![1f64, 2f64, 3f64], vec![4f64, 5f64, 6f64]].to_matrix_2d().unwrap();
+    let t = m.transpose();
+    let r = m.reshape(3, 2).unwrap();
+    assert!(t.shares_buffer(&m) && r.shares_buffer(&m));
+    assert!(t.get_row(2).unwrap() == vec![3f64, 6f64]);
+
+    // writing through one copies the buffer, leaving the others alone
+    let mut c = m.clone();
+    c.get_matrix_mut()[0] = -1f64;
+    assert!(!c.shares_buffer(&m));
+    assert!(m.get_row(0).unwrap() == vec![1f64, 2f64, 3f64]);
+    assert!(t.get_col(0).unwrap() == vec![1f64, 2f64, 3f64]);
+
+    // and the unshared one is written in place
+    let ptr = c.get_matrix().as_ptr();
+    c.get_matrix_mut()[1] = -2f64;
+    assert!(c.get_matrix().as_ptr() == ptr);
+    assert!(c.into_ravel() == vec![-1f64, -2f64, 3f64, 4f64, 5f64, 6f64]);
+    assert!(t.into_ravel() == vec![1f64, 4f64, 2f64, 5f64, 3f64, 6f64]);
+}
+
+#[test]
+fn explicit_layouts() {
+    // the same 2 x 3 matrix in both orders
+    let c = Matrix2d::from_vec_with_layout(vec![1f64, 2f64, 3f64, 4f64, 5f64, 6f64], 2, 3, Layout::RowMajor).unwrap();
+    let f = Matrix2d::from_vec_with_layout(vec![1f64, 4f64, 2f64, 5f64, 3f64, 6f64], 2, 3, Layout::ColMajor).unwrap();
+    assert!(Matrix2d::from_vec_with_layout(vec![1f64; 5], 2, 3, Layout::ColMajor).is_none());
+
+    assert!(c.layout() == Some(Layout::RowMajor) && f.layout() == Some(Layout::ColMajor));
+    assert!(f.get_row(1).unwrap() == vec![4f64, 5f64, 6f64]);
+    assert!(c == f);
+    assert!(f.addition(&c).unwrap().get_row(0).unwrap() == vec![2f64, 4f64, 6f64]);
+
+    assert!(f.as_standard_layout().get_matrix() == c.get_matrix());
+    assert!(c.to_layout(Layout::ColMajor).get_matrix() == f.get_matrix());
+    assert!(c.to_layout(Layout::RowMajor).shares_buffer(&c));
+    assert!(c.transpose().layout() == Some(Layout::ColMajor));
+
+    let zeros = Matrix2d::new_with_layout(3, 2, Layout::ColMajor);
+    assert!(zeros.get_col_stride() == 3 && zeros.is_contiguous());
+}
+
+#[test]
+fn ravel_and_reshape_read_row_major() {
+    let f = Matrix2d::from_vec_with_layout(vec![1f64, 4f64, 2f64, 5f64, 3f64, 6f64], 2, 3, Layout::ColMajor).unwrap();
+    let expected = vec![1f64, 2f64, 3f64, 4f64, 5f64, 6f64];
+
+    assert!(f.ravel() == expected);
+    assert!(f.clone().into_ravel() == expected);
+    assert!(f.transpose().ravel() == vec![1f64, 4f64, 2f64, 5f64, 3f64, 6f64]);
+
+    let r = f.reshape(3, 2).unwrap();
+    assert!(r.layout() == Some(Layout::RowMajor));
+    assert!(r.get_row(0).unwrap() == vec![1f64, 2f64] && r.get_row(2).unwrap() == vec![5f64, 6f64]);
+}
+
+#[test]
+fn dot_keeps_column_major() {
+    let a = Matrix2d::from_vec_with_layout(vec![1f64, 4f64, 2f64, 5f64, 3f64, 6f64], 2, 3, Layout::ColMajor).unwrap();
+    let b = Matrix2d::from_vec_with_layout(vec![1f64, 0f64, 1f64, 0f64, 1f64, 1f64], 3, 2, Layout::ColMajor).unwrap();
+    let expected = vec![vec![4f64, 5f64], vec![10f64, 11f64]].to_matrix_2d().unwrap();
+
+    let product = a.dot(&b).unwrap();
+    assert!(product.layout() == Some(Layout::ColMajor));
+    assert!(product == expected);
+    assert!(a.par_dot(&b).unwrap() == expected);
+
+    // mixed operands give a row-major product
+    let product = a.as_standard_layout().dot(&b).unwrap();
+    assert!(product.layout() == Some(Layout::RowMajor) && product == expected);
+}
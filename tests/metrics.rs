@@ -0,0 +1,114 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::metrics::*;
+
+fn col(v: Vec<f64>) -> Matrix2d {
+    v.to_matrix_2d().unwrap()
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn accuracy_and_confusion() {
+    let y_true = col(vec![0., 1., 2., 2., 1., 0.]);
+    let y_pred = col(vec![0., 2., 2., 2., 1., 1.]);
+
+    assert!(close(accuracy_score(&y_true, &y_pred).unwrap(), 4. / 6.));
+
+    let cm = confusion_matrix(&y_true, &y_pred).unwrap();
+    let expected = vec![vec![1., 1., 0.],
+                        vec![0., 1., 1.],
+                        vec![0., 0., 2.]].to_matrix_2d().unwrap();
+    assert_eq!(cm, expected);
+
+    assert!(accuracy_score(&y_true, &col(vec![0., 1.])).is_none());
+
+    // NaN labels are rejected rather than panicking
+    let nan = col(vec![0., 1., f64::NAN, 2., 1., 0.]);
+    assert!(confusion_matrix(&y_true, &nan).is_none());
+    assert!(precision_recall_f1(&nan, &y_pred, Average::Macro).is_none());
+}
+
+#[test]
+fn precision_recall_averages() {
+    let y_true = col(vec![0., 1., 2., 2., 1., 0.]);
+    let y_pred = col(vec![0., 2., 2., 2., 1., 1.]);
+
+    // per class precision (1, 1/2, 2/3), recall (1/2, 1/2, 1)
+    let (p, r, _) = precision_recall_f1(&y_true, &y_pred, Average::Macro).unwrap();
+    assert!(close(p, (1. + 0.5 + 2. / 3.) / 3.));
+    assert!(close(r, 2. / 3.));
+
+    let (p, r, f) = precision_recall_f1(&y_true, &y_pred, Average::Micro).unwrap();
+    assert!(close(p, 4. / 6.) && close(r, 4. / 6.) && close(f, 4. / 6.));
+
+    // every class has support 2, so weighted matches macro here
+    let weighted = f1_score(&y_true, &y_pred, Average::Weighted).unwrap();
+    assert!(close(weighted, f1_score(&y_true, &y_pred, Average::Macro).unwrap()));
+
+    let y_true = col(vec![1., 1., 1., 0., 0.]);
+    let y_pred = col(vec![1., 0., 1., 1., 0.]);
+    assert!(close(precision_score(&y_true, &y_pred, Average::Binary(1.)).unwrap(), 2. / 3.));
+    assert!(close(recall_score(&y_true, &y_pred, Average::Binary(1.)).unwrap(), 2. / 3.));
+    assert!(close(f1_score(&y_true, &y_pred, Average::Binary(5.)).unwrap(), 0.));
+}
+
+#[test]
+fn roc_and_auc() {
+    let y_true = col(vec![0., 0., 1., 1.]);
+    let scores = col(vec![0.1, 0.4, 0.35, 0.8]);
+
+    let (fpr, tpr, thresholds) = roc_curve(&y_true, &scores, 1.).unwrap();
+    assert_eq!(fpr, vec![0., 0., 0.5, 0.5, 1.]);
+    assert_eq!(tpr, vec![0., 0.5, 0.5, 1., 1.]);
+    assert_eq!(&thresholds[1..], &[0.8, 0.4, 0.35, 0.1]);
+
+    assert!(close(roc_auc_score(&y_true, &scores, 1.).unwrap(), 0.75));
+
+    // tied scores collapse into a single point
+    let tied = col(vec![0.5, 0.5, 0.5, 0.5]);
+    assert!(close(roc_auc_score(&y_true, &tied, 1.).unwrap(), 0.5));
+
+    assert!(roc_curve(&col(vec![1., 1.]), &col(vec![0.2, 0.3]), 1.).is_none());
+
+    // NaN scores sort rather than panic
+    assert!(roc_curve(&y_true, &col(vec![0.1, f64::NAN, 0.35, 0.8]), 1.).is_some());
+    assert!(unique_labels(&col(vec![1., f64::NAN]), &col(vec![0., 1.])).len() == 3);
+}
+
+#[test]
+fn log_loss_matches_columns() {
+    let y_true = col(vec![0., 1., 1.]);
+    let proba = col(vec![0.2, 0.9, 0.6]);
+    let expected = -(0.8f64.ln() + 0.9f64.ln() + 0.6f64.ln()) / 3.;
+    assert!(close(log_loss(&y_true, &proba).unwrap(), expected));
+
+    let two_cols = vec![vec![0.8, 0.2], vec![0.1, 0.9], vec![0.4, 0.6]].to_matrix_2d().unwrap();
+    assert!(close(log_loss(&y_true, &two_cols).unwrap(), expected));
+
+    // a single label can't be matched to a single probability column
+    assert!(log_loss(&col(vec![1., 1., 1.]), &proba).is_none());
+    assert!(log_loss(&col(vec![0., f64::NAN, 1.]), &proba).is_none());
+}
+
+#[test]
+fn regression_errors() {
+    let y_true = vec![vec![3., 1.], vec![-0.5, 2.], vec![2., 3.], vec![7., 4.]].to_matrix_2d().unwrap();
+    let y_pred = vec![vec![2.5, 1.], vec![0., 2.], vec![2., 3.], vec![8., 5.]].to_matrix_2d().unwrap();
+
+    // column errors: [0.5, 0.5, 0, 1] and [0, 0, 0, 1]
+    assert!(close(mean_absolute_error(&y_true, &y_pred).unwrap(), (0.5 + 0.25) / 2.));
+    assert!(close(mean_squared_error(&y_true, &y_pred).unwrap(), (0.375 + 0.25) / 2.));
+    assert!(close(root_mean_squared_error(&y_true, &y_pred).unwrap(), (0.375f64.sqrt() + 0.5) / 2.));
+
+    // ss_tot: 29.1875 and 5
+    let r2 = r2_score(&y_true, &y_pred).unwrap();
+    assert!(close(r2, ((1. - 1.5 / 29.1875) + (1. - 1. / 5.)) / 2.));
+    assert!(close(r2_score(&y_true, &y_true).unwrap(), 1.));
+
+    assert!(r2_score(&y_true, &col(vec![1., 2., 3., 4.])).is_none());
+}
@@ -0,0 +1,92 @@
+extern crate num_rust;
+extern crate rand;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::neighbors::*;
+use num_rust::pairwise::{pairwise_distances, Metric};
+
+fn brute_force(data: &Matrix2d, q: &Matrix2d, k: usize) -> Vec<Vec<usize>> {
+    let d = pairwise_distances(q, data, Metric::Euclidean).unwrap();
+    (0..q.get_rows())
+        .map(|r| {
+            let row = d.get_row(r).unwrap();
+            let mut idx = (0..row.len()).collect::<Vec<usize>>();
+            idx.sort_by(|&a, &b| row[a].partial_cmp(&row[b]).unwrap().then(a.cmp(&b)));
+            idx.truncate(k);
+            idx
+        })
+        .collect()
+}
+
+#[test]
+fn query_matches_brute_force() {
+    let data = Matrix2d::fill_rng(500, 3);
+    let queries = Matrix2d::fill_rng(20, 3);
+    let tree = KdTree::with_leaf_size(&data, 8);
+
+    let (indices, distances) = tree.query(&queries, 5).unwrap();
+    assert!(indices == brute_force(&data, &queries, 5));
+    for d in distances.iter() {
+        assert!(d.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+#[test]
+fn query_radius_test() {
+    let data = vec![vec![0., 0.], vec![1., 0.], vec![0., 2.], vec![3., 3.]].to_matrix_2d().unwrap();
+    let tree = KdTree::with_leaf_size(&data, 1);
+    let q = vec![vec![0., 0.], vec![10., 10.]].to_matrix_2d().unwrap();
+
+    let (indices, distances) = tree.query_radius(&q, 2.).unwrap();
+    assert!(indices[0] == vec![0, 1, 2]);
+    assert!(distances[0] == vec![0., 1., 2.]);
+    assert!(indices[1].is_empty());
+}
+
+#[test]
+fn query_rejects_bad_input() {
+    let data = Matrix2d::fill_rng(10, 2);
+    let tree = KdTree::new(&data);
+    assert!(tree.len() == 10);
+    assert!(tree.query(&Matrix2d::fill_rng(1, 3), 1).is_none());
+    assert!(tree.query(&Matrix2d::fill_rng(1, 2), 11).is_none());
+}
+
+#[test]
+fn classifier() {
+    let x = vec![vec![0., 0.], vec![0., 1.], vec![1., 0.], vec![5., 5.], vec![5., 6.], vec![6., 5.]].to_matrix_2d().unwrap();
+    let y = vec![1., 1., 1., 2., 2., 2.].to_matrix_2d().unwrap();
+    let mut knn = KNeighborsClassifier::new(3, Weights::Uniform);
+    knn.fit(&x, &y).unwrap();
+
+    let q = vec![vec![0.5, 0.5], vec![5.5, 5.5], vec![4., 4.]].to_matrix_2d().unwrap();
+    assert!(knn.predict(&q).unwrap().ravel() == vec![1., 2., 2.]);
+    assert!(knn.predict_proba(&q).unwrap().get_row(0).unwrap() == vec![1., 0.]);
+
+    let mut weighted = KNeighborsClassifier::new(6, Weights::Distance);
+    weighted.fit(&x, &y).unwrap();
+    assert!(weighted.predict(&vec![vec![1., 1.]].to_matrix_2d().unwrap()).unwrap().ravel() == vec![1.]);
+    assert!(weighted.get_classes() == [1., 2.]);
+
+    let mut nan_y = y.clone();
+    nan_y.get_matrix_mut()[0] = f64::NAN;
+    assert!(KNeighborsClassifier::new(3, Weights::Uniform).fit(&x, &nan_y).is_none());
+}
+
+#[test]
+fn regressor() {
+    let x = vec![0., 1., 2., 3., 4.].to_matrix_2d().unwrap();
+    let y = vec![vec![0., 10.], vec![1., 20.], vec![2., 30.], vec![3., 40.], vec![4., 50.]].to_matrix_2d().unwrap();
+    let mut knn = KNeighborsRegressor::new(2, Weights::Uniform);
+    knn.fit(&x, &y).unwrap();
+
+    let pred = knn.predict(&vec![0.4, 3.].to_matrix_2d().unwrap()).unwrap();
+    assert!(pred.get_row(0).unwrap() == vec![0.5, 15.]);
+
+    let mut weighted = KNeighborsRegressor::new(2, Weights::Distance);
+    weighted.fit(&x, &y).unwrap();
+    let pred = weighted.predict(&vec![0.25, 3.].to_matrix_2d().unwrap()).unwrap();
+    assert!((pred.get_row(0).unwrap()[0] - 0.25).abs() < 1e-12);
+    assert!(pred.get_row(1).unwrap() == vec![3., 40.]);
+}
@@ -0,0 +1,116 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::loss;
+use num_rust::nn::*;
+
+fn xor() -> (Matrix2d, Matrix2d) {
+    let x = vec![vec![0., 0.], vec![0., 1.], vec![1., 0.], vec![1., 1.]];
+    let y = vec![vec![0.], vec![1.], vec![1.], vec![0.]];
+    (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+}
+
+#[test]
+fn dense_forward() {
+    let w = vec![vec![1., 2.], vec![3., 4.]].to_matrix_2d().unwrap();
+    let b = vec![vec![0.5, -1.]].to_matrix_2d().unwrap();
+    let mut dense = Dense::from_weights(w, b).unwrap();
+
+    let x = vec![vec![1., 1.], vec![0., 2.]].to_matrix_2d().unwrap();
+    let out = vec![vec![4.5, 5.], vec![6.5, 7.]].to_matrix_2d().unwrap();
+    assert!(dense.forward(&x).unwrap() == out);
+    assert!(dense.forward(&Matrix2d::new(2, 3)).is_none());
+
+    let bad_bias = vec![vec![0.5, -1., 2.]].to_matrix_2d().unwrap();
+    assert!(Dense::from_weights(Matrix2d::new(2, 2), bad_bias).is_none());
+}
+
+#[test]
+fn dense_backward_matches_finite_differences() {
+    let w = vec![vec![0.2, -0.4], vec![0.7, 0.1], vec![-0.3, 0.5]].to_matrix_2d().unwrap();
+    let b = vec![vec![0.1, -0.2]].to_matrix_2d().unwrap();
+    let x = vec![vec![1., -2., 0.5], vec![0.3, 0.8, -1.]].to_matrix_2d().unwrap();
+    let y = vec![vec![1., 0.], vec![0., 1.]].to_matrix_2d().unwrap();
+
+    let loss_at = |w: &Matrix2d| {
+        let mut net = Sequential::new(loss::mean_squared_error, 0.);
+        net.add(Box::new(Dense::from_weights(w.clone(), b.clone()).unwrap()));
+        net.add(Box::new(Tanh::new()));
+        loss::mean_squared_error(&net.forward(&x).unwrap(), &y).unwrap().0
+    };
+
+    let mut dense = Dense::from_weights(w.clone(), b.clone()).unwrap();
+    let mut tanh = Tanh::new();
+    let out = tanh.forward(&dense.forward(&x).unwrap()).unwrap();
+    let (_, grad) = loss::mean_squared_error(&out, &y).unwrap();
+    dense.backward(&tanh.backward(&grad));
+    let analytic = dense.params_and_grads()[0].1.ravel();
+
+    let h = 1e-6;
+    for i in 0..analytic.len() {
+        let mut up = w.ravel();
+        let mut down = w.ravel();
+        up[i] += h;
+        down[i] -= h;
+        let numeric = (loss_at(&up.reshape(3, 2).unwrap()) - loss_at(&down.reshape(3, 2).unwrap())) / (2. * h);
+        assert!((analytic[i] - numeric).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn sequential_learns_xor() {
+    let (x, y) = xor();
+    // fixed weights, spread like `Dense::new` draws them, so the test can't
+    // hit an unlucky start
+    let w1 = (0..2).map(|i| (0..8).map(|j| (1.7 * (8 * i + j) as f64).sin()).collect())
+        .collect::<Vec<Vec<f64>>>().to_matrix_2d().unwrap();
+    let w2 = (0..8).map(|j| vec![0.8 * (2.3 * j as f64 + 1.).cos()])
+        .collect::<Vec<Vec<f64>>>().to_matrix_2d().unwrap();
+    let mut net = Sequential::new(loss::binary_cross_entropy_with_logits, 0.5);
+    net.add(Box::new(Dense::from_weights(w1, Matrix2d::new(1, 8)).unwrap()));
+    net.add(Box::new(Tanh::new()));
+    net.add(Box::new(Dense::from_weights(w2, Matrix2d::new(1, 1)).unwrap()));
+
+    for _ in 0..2000 {
+        net.train_batch(&x, &y).unwrap();
+    }
+
+    let pred = net.predict(&x).unwrap().ravel();
+    assert!(pred[0] < 0. && pred[1] > 0. && pred[2] > 0. && pred[3] < 0.);
+}
+
+#[test]
+fn fit_reduces_loss() {
+    let x = (0..64).map(|i| vec![i as f64 / 64., 1. - i as f64 / 64.]).collect::<Vec<Vec<f64>>>();
+    let y = (0..64).map(|i| vec![2. * i as f64 / 64. - 0.5]).collect::<Vec<Vec<f64>>>();
+    let (x, y) = (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap());
+
+    // fixed weights so no hidden unit starts out dead
+    let w1 = vec![vec![0.5, -0.3, 0.2, 0.4], vec![-0.2, 0.6, 0.3, -0.5]].to_matrix_2d().unwrap();
+    let w2 = vec![0.3, -0.4, 0.5, 0.2].to_matrix_2d().unwrap();
+    let mut net = Sequential::new(loss::mean_squared_error, 0.1);
+    net.add(Box::new(Dense::from_weights(w1, vec![vec![0.1; 4]].to_matrix_2d().unwrap()).unwrap()));
+    net.add(Box::new(Relu::new()));
+    net.add(Box::new(Dense::from_weights(w2, Matrix2d::new(1, 1)).unwrap()));
+
+    let history = net.fit(&x, &y, 50, 8).unwrap();
+    assert!(history.len() == 50);
+    assert!(history[49] < history[0]);
+}
+
+#[test]
+fn fit_rejects_mismatched_rows() {
+    let (x, _) = xor();
+    let y = vec![0., 1.].to_matrix_2d().unwrap();
+
+    let mut net = Sequential::new(loss::mean_squared_error, 0.1);
+    net.add(Box::new(Sigmoid::new()));
+    assert!(net.fit(&x, &y, 1, 2).is_none());
+
+    // inputs that don't fit the first layer
+    let mut net = Sequential::new(loss::mean_squared_error, 0.1);
+    net.add(Box::new(Dense::new(3, 1)));
+    assert!(net.predict(&x).is_none());
+    assert!(net.fit(&x, &xor().1, 1, 2).is_none());
+}
@@ -0,0 +1,137 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::loss;
+use num_rust::nn::*;
+use num_rust::optim::*;
+use num_rust::utils::frobenius_norm;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+// minimises ||x - target||^2 and returns the final x
+fn minimise(opt: &mut dyn Optimizer, steps: usize) -> Matrix2d {
+    let target = vec![vec![1., -2.], vec![3., 0.5]].to_matrix_2d().unwrap();
+    let mut x = Matrix2d::new(2, 2);
+    for _ in 0..steps {
+        let grad = x.subtract(&target).unwrap().scale(2.);
+        opt.step(&mut [&mut x], &[grad]).unwrap();
+    }
+    x.subtract(&target).unwrap()
+}
+
+#[test]
+fn sgd_step() {
+    let mut x = vec![1., 2.].to_matrix_2d().unwrap();
+    let g = vec![0.5, -1.].to_matrix_2d().unwrap();
+
+    let mut sgd = Sgd::new(0.1);
+    sgd.step(&mut [&mut x], std::slice::from_ref(&g)).unwrap();
+    assert!(x == vec![0.95, 2.1].to_matrix_2d().unwrap());
+
+    // a gradient of the wrong shape, or a missing one, leaves x alone
+    assert!(sgd.step(&mut [&mut x], &[g.transpose()]).is_none());
+    assert!(sgd.step(&mut [&mut x], &[]).is_none());
+    assert!(x == vec![0.95, 2.1].to_matrix_2d().unwrap());
+
+    let mut y = vec![0.].to_matrix_2d().unwrap();
+    let mut momentum = Sgd::with_momentum(1., 0.5, false);
+    momentum.step(&mut [&mut y], &[vec![1.].to_matrix_2d().unwrap()]);
+    momentum.step(&mut [&mut y], &[vec![1.].to_matrix_2d().unwrap()]);
+    assert!(close(y.get_matrix()[0], -2.5));
+
+    let mut z = vec![0.].to_matrix_2d().unwrap();
+    let mut nesterov = Sgd::with_momentum(1., 0.5, true);
+    nesterov.step(&mut [&mut z], &[vec![1.].to_matrix_2d().unwrap()]);
+    assert!(close(z.get_matrix()[0], -1.5));
+}
+
+#[test]
+fn optimizers_converge() {
+    let tolerance = 1e-3;
+    assert!(frobenius_norm(&minimise(&mut Sgd::new(0.1), 200)) < tolerance);
+    assert!(frobenius_norm(&minimise(&mut Sgd::with_momentum(0.05, 0.9, false), 300)) < tolerance);
+    assert!(frobenius_norm(&minimise(&mut Sgd::with_momentum(0.05, 0.9, true), 300)) < tolerance);
+    assert!(frobenius_norm(&minimise(&mut RmsProp::new(0.01), 1000)) < 0.05);
+    assert!(frobenius_norm(&minimise(&mut Adam::new(0.1), 1000)) < tolerance);
+}
+
+#[test]
+fn adam_first_step_is_learning_rate() {
+    let mut x = vec![1., -1.].to_matrix_2d().unwrap();
+    let mut adam = Adam::new(0.01);
+    adam.step(&mut [&mut x], &[vec![3., -0.2].to_matrix_2d().unwrap()]);
+
+    assert!((x.get_matrix()[0] - 0.99).abs() < 1e-6);
+    assert!((x.get_matrix()[1] + 0.99).abs() < 1e-6);
+}
+
+#[test]
+fn adamw_decays_weights() {
+    let mut x = vec![2.].to_matrix_2d().unwrap();
+    let mut adamw = AdamW::new(0.1, 0.5);
+    adamw.step(&mut [&mut x], &[vec![0.].to_matrix_2d().unwrap()]);
+
+    assert!(close(x.get_matrix()[0], 1.9));
+}
+
+#[test]
+fn transposed_gradient() {
+    let mut x = Matrix2d::new(2, 3);
+    let g = vec![vec![1., 2.], vec![3., 4.], vec![5., 6.]].to_matrix_2d().unwrap().transpose();
+
+    Sgd::new(1.).step(&mut [&mut x], std::slice::from_ref(&g)).unwrap();
+    assert!(x.get_row(0).unwrap() == vec![-1., -3., -5.]);
+}
+
+#[test]
+fn schedules() {
+    let step = StepDecay::new(1., 0.5, 10).unwrap();
+    assert!(close(step.get_learning_rate(9), 1.));
+    assert!(close(step.get_learning_rate(25), 0.25));
+
+    let exp = ExponentialDecay::new(2., 0.9);
+    assert!(close(exp.get_learning_rate(2), 1.62));
+
+    let cos = CosineAnnealing::new(1., 0.1, 10).unwrap();
+    assert!(close(cos.get_learning_rate(0), 1.));
+    assert!(close(cos.get_learning_rate(5), 0.55));
+    assert!(close(cos.get_learning_rate(10), 0.1));
+    assert!(close(cos.get_learning_rate(20), 0.1));
+
+    assert!(StepDecay::new(1., 0.5, 0).is_none());
+    assert!(CosineAnnealing::new(1., 0.1, 0).is_none());
+}
+
+#[test]
+fn clip_grad_norm_test() {
+    let mut grads = vec![vec![3.].to_matrix_2d().unwrap(), vec![4.].to_matrix_2d().unwrap()];
+
+    assert!(close(clip_grad_norm(&mut grads, 10.), 5.));
+    assert!(grads[0].get_matrix()[0] == 3.);
+
+    assert!(close(clip_grad_norm(&mut grads, 1.), 5.));
+    assert!(close(grads[0].get_matrix()[0], 0.6));
+    assert!(close(grads[1].get_matrix()[0], 0.8));
+}
+
+#[test]
+fn sequential_with_adam() {
+    let x = vec![vec![0., 0.], vec![0., 1.], vec![1., 0.], vec![1., 1.]].to_matrix_2d().unwrap();
+    let y = vec![vec![0.], vec![1.], vec![1.], vec![0.]].to_matrix_2d().unwrap();
+
+    let mut net = Sequential::with_optimizer(loss::binary_cross_entropy_with_logits, Box::new(Adam::new(0.05)));
+    net.add(Box::new(Dense::new(2, 8)));
+    net.add(Box::new(Tanh::new()));
+    net.add(Box::new(Dense::new(8, 1)));
+
+    for _ in 0..500 {
+        net.train_batch(&x, &y).unwrap();
+    }
+    assert!(net.get_optimizer_mut().get_learning_rate() == 0.05);
+
+    let pred = net.predict(&x).unwrap().ravel();
+    assert!(pred[0] < 0. && pred[1] > 0. && pred[2] > 0. && pred[3] < 0.);
+}
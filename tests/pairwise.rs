@@ -0,0 +1,74 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::pairwise::*;
+
+fn close(a: &Matrix2d, b: &[f64]) -> bool {
+    a.ravel().iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-9)
+}
+
+fn points() -> (Matrix2d, Matrix2d) {
+    let a = vec![vec![0., 0.], vec![3., 4.]].to_matrix_2d().unwrap();
+    let b = vec![vec![1., 0.], vec![0., 2.], vec![3., 4.]].to_matrix_2d().unwrap();
+    (a, b)
+}
+
+#[test]
+fn distances() {
+    let (a, b) = points();
+
+    let d = pairwise_distances(&a, &b, Metric::Euclidean).unwrap();
+    assert!(d.get_rows() == 2 && d.get_cols() == 3);
+    assert!(close(&d, &[1., 2., 5., 20f64.sqrt(), 13f64.sqrt(), 0.]));
+
+    let d = pairwise_distances(&a, &b, Metric::SquaredEuclidean).unwrap();
+    assert!(close(&d, &[1., 4., 25., 20., 13., 0.]));
+
+    let d = pairwise_distances(&a, &b, Metric::Manhattan).unwrap();
+    assert!(close(&d, &[1., 2., 7., 6., 5., 0.]));
+
+    let d = pairwise_distances(&a, &b, Metric::Chebyshev).unwrap();
+    assert!(close(&d, &[1., 2., 4., 4., 3., 0.]));
+
+    // zero vectors are treated as orthogonal to everything
+    let d = pairwise_distances(&a, &b, Metric::Cosine).unwrap();
+    assert!(close(&d, &[1., 1., 1., 0.4, 0.2, 0.]));
+}
+
+#[test]
+fn transposed_input() {
+    let (a, b) = points();
+    let at = vec![vec![0., 3.], vec![0., 4.]].to_matrix_2d().unwrap().transpose();
+
+    assert!(pairwise_distances(&at, &b, Metric::Manhattan).unwrap() ==
+            pairwise_distances(&a, &b, Metric::Manhattan).unwrap());
+    assert!(pairwise_distances(&at, &b, Metric::Euclidean).unwrap() ==
+            pairwise_distances(&a, &b, Metric::Euclidean).unwrap());
+}
+
+#[test]
+fn kernels() {
+    let (a, b) = points();
+
+    let k = pairwise_kernels(&a, &b, Kernel::Linear).unwrap();
+    assert!(close(&k, &[0., 0., 0., 3., 8., 25.]));
+
+    let k = pairwise_kernels(&a, &b, Kernel::Rbf { gamma: 0.5 }).unwrap();
+    assert!(close(&k, &[(-0.5f64).exp(), (-2f64).exp(), (-12.5f64).exp(), (-10f64).exp(), (-6.5f64).exp(), 1.]));
+
+    let k = pairwise_kernels(&a, &b, Kernel::Polynomial { gamma: 1., coef0: 1., degree: 2 }).unwrap();
+    assert!(close(&k, &[1., 1., 1., 16., 81., 676.]));
+
+    let k = pairwise_kernels(&a, &b, Kernel::Sigmoid { gamma: 0.1, coef0: 0. }).unwrap();
+    assert!(close(&k, &[0., 0., 0., 0.3f64.tanh(), 0.8f64.tanh(), 2.5f64.tanh()]));
+}
+
+#[test]
+fn mismatched_columns() {
+    let (a, _) = points();
+    let c = vec![1., 2., 3.].to_matrix_2d().unwrap().transpose();
+
+    assert!(pairwise_distances(&a, &c, Metric::Euclidean).is_none());
+    assert!(pairwise_kernels(&a, &c, Kernel::Linear).is_none());
+}
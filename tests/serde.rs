@@ -0,0 +1,57 @@
+#![cfg(feature = "serde")]
+
+extern crate num_rust;
+extern crate serde_json;
+extern crate bincode;
+extern crate rmp_serde;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+
+fn sample() -> Matrix2d {
+    vec![vec![1.5, -2., 3.], vec![4., 0.25, -6e10]].to_matrix_2d().unwrap()
+}
+
+#[test]
+fn json_round_trip() {
+    let m = sample();
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(json, r#"{"n_rows":2,"n_cols":3,"data":[1.5,-2.0,3.0,4.0,0.25,-60000000000.0]}"#);
+    assert_eq!(serde_json::from_str::<Matrix2d>(&json).unwrap(), m);
+}
+
+#[test]
+fn binary_round_trips() {
+    let m = sample();
+
+    let bytes = bincode::serialize(&m).unwrap();
+    assert_eq!(bincode::deserialize::<Matrix2d>(&bytes).unwrap(), m);
+
+    let bytes = rmp_serde::to_vec(&m).unwrap();
+    assert_eq!(rmp_serde::from_slice::<Matrix2d>(&bytes).unwrap(), m);
+    let bytes = rmp_serde::to_vec_named(&m).unwrap();
+    assert_eq!(rmp_serde::from_slice::<Matrix2d>(&bytes).unwrap(), m);
+}
+
+#[test]
+fn transposed_layout_is_normalized() {
+    let t = sample().transpose();
+    let json = serde_json::to_string(&t).unwrap();
+    assert!(json.contains(r#""data":[1.5,4.0,-2.0,0.25,3.0,-60000000000.0]"#));
+
+    let back = serde_json::from_str::<Matrix2d>(&json).unwrap();
+    assert_eq!((back.get_row_stride(), back.get_col_stride()), (2, 1));
+    for row in 0..3 {
+        assert_eq!(back.get_row(row), t.get_row(row));
+    }
+
+    let back = bincode::deserialize::<Matrix2d>(&bincode::serialize(&t).unwrap()).unwrap();
+    assert_eq!(back.get_col(1), t.get_col(1));
+}
+
+#[test]
+fn rejects_mismatched_length() {
+    let err = serde_json::from_str::<Matrix2d>(r#"{"n_rows":2,"n_cols":2,"data":[1.0,2.0,3.0]}"#).unwrap_err();
+    assert!(err.to_string().contains("don't fill a 2 x 2 matrix"));
+    assert!(serde_json::from_str::<Matrix2d>(r#"{"n_rows":2,"data":[1.0,2.0]}"#).is_err());
+}
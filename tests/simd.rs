@@ -0,0 +1,92 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::simd::{self, Level};
+use num_rust::utils::unrolled_sum;
+
+fn levels() -> Vec<Level> {
+    vec![Level::Scalar, Level::Sse2, Level::Avx2, Level::Avx512].into_iter()
+        .filter(|&l| l <= simd::detected_level())
+        .collect()
+}
+
+// the order `utils::unrolled_sum` has always added in
+fn eightfold_sum(xs: &[f64]) -> f64 {
+    let mut p = [0.; 8];
+    for (i, &x) in xs[..xs.len() / 8 * 8].iter().enumerate() {
+        p[i % 8] += x;
+    }
+    let sum = (0..4).fold(0., |s, j| s + (p[j] + p[j + 4]));
+    xs[xs.len() / 8 * 8..].iter().fold(sum, |s, &x| s + x)
+}
+
+// lengths around every vector width, to cover the tails
+const LENS: [usize; 6] = [0, 1, 7, 17, 64, 1001];
+
+#[test]
+fn kernels_at_every_level() {
+    // `Matrix2d` and `unrolled_sum` run on the kernels and must not change
+    // with the level; everything below runs in one test as the level is global
+    let a = Matrix2d::from_vec(&vec![(0..101).map(|i| (i as f64 * 0.3).cos()).collect()]);
+    let b = a.apply_fn(|x| 2. - x * x);
+    let ops = |a: &Matrix2d, b: &Matrix2d| {
+        (a.addition(b).unwrap().ravel(), a.subtract(b).unwrap().ravel(), a.mult(b).unwrap().ravel(),
+         a.scale(0.7).ravel(), unrolled_sum(a.get_matrix()))
+    };
+    simd::set_level(Some(Level::Scalar));
+    let reference = ops(&a, &b);
+    assert!(reference.4 == eightfold_sum(a.get_matrix()));
+
+    for &level in levels().iter() {
+        simd::set_level(Some(level));
+        assert!(simd::level() == level);
+        assert!(ops(&a, &b) == reference);
+
+        for &len in LENS.iter() {
+            let u = (0..len).map(|i| (i as f64 * 0.37).sin() * 10.).collect::<Vec<f64>>();
+            let v = (0..len).map(|i| 1. + (i % 13) as f64).collect::<Vec<f64>>();
+            let mut dst = vec![0.; len];
+
+            simd::add(&u, &v, &mut dst);
+            assert!(dst.iter().zip(u.iter().zip(v.iter())).all(|(&d, (&x, &y))| d == x + y));
+            simd::sub(&u, &v, &mut dst);
+            assert!(dst.iter().zip(u.iter().zip(v.iter())).all(|(&d, (&x, &y))| d == x - y));
+            simd::mul(&u, &v, &mut dst);
+            assert!(dst.iter().zip(u.iter().zip(v.iter())).all(|(&d, (&x, &y))| d == x * y));
+            simd::div(&u, &v, &mut dst);
+            assert!(dst.iter().zip(u.iter().zip(v.iter())).all(|(&d, (&x, &y))| d == x / y));
+            simd::scale(&u, -2.5, &mut dst);
+            assert!(dst.iter().zip(u.iter()).all(|(&d, &x)| d == -2.5 * x));
+
+            simd::exp(&u, &mut dst);
+            assert!(dst.iter().zip(u.iter()).all(|(&d, &x)| ((d - x.exp()) / x.exp()).abs() < 1e-14));
+
+            let sum = u.iter().sum::<f64>();
+            assert!((simd::sum(&u) - sum).abs() < 1e-10);
+            assert!(simd::sum(&u) == eightfold_sum(&u));
+            assert!(simd::max(&u) == u.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+
+            let u32 = u.iter().map(|&x| x as f32).collect::<Vec<f32>>();
+            let v32 = v.iter().map(|&x| x as f32).collect::<Vec<f32>>();
+            let mut dst32 = vec![0f32; len];
+            simd::mul(&u32, &v32, &mut dst32);
+            assert!(dst32.iter().zip(u32.iter().zip(v32.iter())).all(|(&d, (&x, &y))| d == x * y));
+            simd::exp(&u32, &mut dst32);
+            assert!(dst32.iter().zip(u32.iter()).all(|(&d, &x)| ((d - x.exp()) / x.exp()).abs() < 1e-6));
+            assert!((simd::sum(&u32) - sum as f32).abs() < 1e-3);
+            assert!(simd::max(&u32) == u32.iter().cloned().fold(f32::NEG_INFINITY, f32::max));
+        }
+
+        // out of the vectorized range, and NaNs, are left to std
+        let special = [f64::NAN, -1000., -708.5, 709.5, 1000., f64::INFINITY, f64::NEG_INFINITY, 0.];
+        let mut dst = [0.; 8];
+        simd::exp(&special, &mut dst);
+        assert!(dst[0].is_nan());
+        assert!(dst[1..].iter().zip(special[1..].iter()).all(|(&d, &x)| d == x.exp()));
+        assert!(simd::max(&special) == f64::INFINITY);
+        assert!(simd::max(&[f64::NAN, -3., f64::NAN]) == -3.);
+    }
+    simd::set_level(None);
+    assert!(simd::level() == simd::detected_level());
+}
+
@@ -0,0 +1,41 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::sparse::CsrMatrix;
+
+#[test]
+fn builds_from_triplets() {
+    let s = CsrMatrix::from_triplets(2, 3, &[(1, 2, 4.), (0, 1, 1.), (1, 0, 2.), (1, 2, 1.)]).unwrap();
+    assert_eq!(s.nnz(), 3);
+    assert_eq!(s.triplets(), vec![(0, 1, 1.), (1, 0, 2.), (1, 2, 5.)]);
+    assert_eq!(s.get(0, 0), Some(0.));
+    assert_eq!(s.get(2, 0), None);
+    assert_eq!(s.to_dense(), vec![vec![0., 1., 0.], vec![2., 0., 5.]].to_matrix_2d().unwrap());
+
+    assert!(CsrMatrix::from_triplets(2, 2, &[(0, 2, 1.)]).is_none());
+}
+
+#[test]
+fn dense_round_trip_and_products() {
+    let m = vec![vec![1., 0., 2.], vec![0., 0., 3.]].to_matrix_2d().unwrap();
+    let s = CsrMatrix::from_dense(&m);
+    assert_eq!(s.nnz(), 3);
+    assert_eq!(s.to_dense(), m);
+    assert_eq!(s.transpose().to_dense().get_row(2).unwrap(), vec![2., 3.]);
+
+    let x = vec![vec![1., 2.], vec![3., 4.], vec![5., 6.]].to_matrix_2d().unwrap();
+    assert_eq!(s.dot(&x).unwrap(), m.dot(&x).unwrap());
+    assert_eq!(s.dot(&x.transpose().transpose()).unwrap(), m.dot(&x).unwrap());
+    assert!(s.dot(&m).is_none());
+}
+
+#[test]
+fn zero_dimensions() {
+    let s = CsrMatrix::from_triplets(2, 0, &[]).unwrap();
+    assert_eq!(s.to_dense().get_rows(), 2);
+    assert_eq!(s.to_dense().get_cols(), 0);
+
+    let m = CsrMatrix::from_dense(&vec![vec![1., 2.]].to_matrix_2d().unwrap());
+    assert_eq!(m.dot(&Matrix2d::new(2, 0)).unwrap().get_cols(), 0);
+}
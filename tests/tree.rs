@@ -0,0 +1,66 @@
+extern crate num_rust;
+
+use num_rust::Matrix2d;
+use num_rust::ext::traits::ToMatrix2d;
+use num_rust::tree::*;
+
+fn xor() -> (Matrix2d, Matrix2d) {
+    let x = vec![vec![0., 0.], vec![0., 1.], vec![1., 0.], vec![1., 1.]];
+    let y = vec![3., 4., 4., 3.];
+    (x.to_matrix_2d().unwrap(), y.to_matrix_2d().unwrap())
+}
+
+#[test]
+fn classifier_fits_xor() {
+    let (x, y) = xor();
+    for &criterion in [Criterion::Gini, Criterion::Entropy].iter() {
+        let mut tree = DecisionTreeClassifier::new(criterion);
+        tree.fit(&x, &y).unwrap();
+
+        assert!(tree.predict(&x).unwrap() == y);
+        assert!(tree.get_classes() == [3., 4.]);
+        assert!(tree.get_depth() == 2 && tree.get_n_leaves() == 4);
+    }
+}
+
+#[test]
+fn classifier_limits() {
+    let (x, y) = xor();
+    let mut stump = DecisionTreeClassifier::with_params(Criterion::Gini, Some(1), 2, 1);
+    stump.fit(&x, &y).unwrap();
+    assert!(stump.get_depth() <= 1);
+
+    let mut leafy = DecisionTreeClassifier::with_params(Criterion::Gini, None, 2, 3);
+    leafy.fit(&x, &y).unwrap();
+    assert!(leafy.get_n_leaves() == 1);
+    assert!(leafy.predict_proba(&x).unwrap().get_row(0).unwrap() == vec![0.5, 0.5]);
+
+    let mut mse = DecisionTreeClassifier::new(Criterion::Mse);
+    assert!(mse.fit(&x, &y).is_none());
+    assert!(stump.predict(&vec![1., 2., 3.].to_matrix_2d().unwrap().transpose()).is_none());
+
+    // a NaN feature doesn't panic the split search
+    let mut nan_x = x.clone();
+    nan_x.get_matrix_mut()[0] = f64::NAN;
+    assert!(DecisionTreeClassifier::new(Criterion::Gini).fit(&nan_x, &y).is_some());
+
+    // a NaN label is rejected
+    let mut nan_y = y.clone();
+    nan_y.get_matrix_mut()[1] = f64::NAN;
+    assert!(DecisionTreeClassifier::new(Criterion::Gini).fit(&x, &nan_y).is_none());
+}
+
+#[test]
+fn regressor_step_function() {
+    let x = vec![1., 2., 3., 4., 5., 6.].to_matrix_2d().unwrap();
+    let y = vec![1., 1., 1., 5., 5., 7.].to_matrix_2d().unwrap();
+
+    let mut tree = DecisionTreeRegressor::new();
+    tree.fit(&x, &y).unwrap();
+    assert!(tree.predict(&x).unwrap() == y);
+
+    let mut stump = DecisionTreeRegressor::with_params(Some(1), 2, 1);
+    stump.fit(&x, &y).unwrap();
+    let pred = stump.predict(&vec![0., 3.4, 3.6, 10.].to_matrix_2d().unwrap()).unwrap();
+    assert!(pred.ravel() == vec![1., 1., 17. / 3., 17. / 3.]);
+}
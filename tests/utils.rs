@@ -24,3 +24,52 @@ fn vec_fn_op_threaded_test() {
 fn frobenius_norm_test() {
     assert!((30f64).sqrt() == frobenius_norm(&vec![1.0, 2.0, 3.0, 4.0].to_matrix_2d().unwrap()));
 }
+
+#[test]
+fn vec_bin_op_par_test() {
+    let u = (0..10000).map(|x| x as f64).collect::<Vec<f64>>();
+    let v = vec![2.; 10000];
+    let mut dst = vec![0.; 10000];
+
+    for &chunk_size in [0, 7, 1024, 20000].iter() {
+        vec_bin_op_par(&u, &v, &mut dst, chunk_size, &|x, y| x * y);
+        assert!(dst == vec_bin_op(&u, &v, |x, y| x * y));
+    }
+
+    for &chunk_size in [0, 1, 100].iter() {
+        vec_fn_op_par(&u, &mut dst, chunk_size, &|x| x - 1.);
+        assert!(dst[0] == -1. && dst[9999] == 9998.);
+    }
+}
+
+#[test]
+fn par_chunk_size_test() {
+    assert!(par_chunk_size(10) >= 10);
+    set_par_chunk_size(Some(64));
+    assert!(par_chunk_size(1 << 20) == 64);
+    // reductions keep their own chunk size
+    assert!(reduce_chunk_size() == 4096);
+    set_par_chunk_size(None);
+    assert!(par_chunk_size(1 << 30) >= (1 << 30) / 1024);
+
+    set_reduce_chunk_size(Some(100));
+    assert!(reduce_chunk_size() == 100);
+    set_reduce_chunk_size(None);
+    assert!(reduce_chunk_size() == 4096);
+}
+
+#[test]
+fn par_reduction_test() {
+    let u = (0..10000).map(|x| x as f64).collect::<Vec<f64>>();
+    let v = vec![2.; 10000];
+
+    for &chunk_size in [0, 7, 1024, 20000].iter() {
+        assert!(par_sum(&u, chunk_size) == 49995000.);
+        assert!(par_dot(&u, &v, chunk_size) == 99990000.);
+    }
+
+    // the tree only depends on the length and chunk size
+    let w = (0..10000).map(|x| 1. / (x as f64 + 1.)).collect::<Vec<f64>>();
+    let sum = par_sum(&w, 64);
+    assert!((0..10).all(|_| par_sum(&w, 64) == sum));
+}